@@ -6,9 +6,11 @@ import {
   Contract,
   Address,
   nativeToScVal,
+  scValToNative,
   rpc,
   Horizon,
   Operation,
+  xdr,
 } from '@stellar/stellar-sdk';
 
 // Environment variables
@@ -19,10 +21,17 @@ const STELLAR_NETWORK_PASSPHRASE =
 const DAO_CONTRACT_ID = process.env.PUBLIC_DAO_CONTRACT_ID || '';
 const KALE_CONTRACT_ID = process.env.PUBLIC_KALE_CONTRACT_ID || '';
 
+// Mirrors the contract's SUBMISSION_CUTOFF: resolve_after must be at least
+// this many seconds ahead
+const SUBMISSION_CUTOFF = 300;
+const DEFAULT_HORIZON = 60 * 60;
+
 interface PrepareTransactionRequest {
   publicKey: string;
   teamName: string;
   stakePercentage: number;
+  label?: string; // optional client label, see get_prediction_id
+  horizonSeconds?: number; // seconds until the prediction resolves
   prediction: {
     asset: string;
     direction: boolean; // true = up, false = down
@@ -32,8 +41,10 @@ interface PrepareTransactionRequest {
 interface PrepareTransactionResponse {
   xdr: string;
   networkPassphrase: string;
-  predictionId: string;
-  operationType?: string;
+  predictionId?: string; // u64 returned by make_prediction in simulation
+  resolveAfter?: number;
+  operationType?: string; // stake_kale has to land before make_prediction
+  stakeAmount?: string;
 }
 
 export async function POST(request: NextRequest): Promise<NextResponse> {
@@ -67,6 +78,16 @@ export async function POST(request: NextRequest): Promise<NextResponse> {
       );
     }
 
+    const horizon = body.horizonSeconds ?? DEFAULT_HORIZON;
+    if (!Number.isInteger(horizon) || horizon < SUBMISSION_CUTOFF) {
+      return NextResponse.json(
+        {
+          error: `Horizon must be a whole number of seconds of at least ${SUBMISSION_CUTOFF}`,
+        },
+        { status: 400 },
+      );
+    }
+
     if (!DAO_CONTRACT_ID || !KALE_CONTRACT_ID) {
       return NextResponse.json(
         {
@@ -103,32 +124,8 @@ export async function POST(request: NextRequest): Promise<NextResponse> {
     const daoContract = new Contract(DAO_CONTRACT_ID);
     const kaleContract = new Contract(KALE_CONTRACT_ID);
 
-    // Get actual KALE balance from the contract
-    let kaleBalance: bigint;
-    try {
-      // For now, assume user has sufficient KALE balance
-      // In production, you would properly parse the contract balance result
-      kaleBalance = BigInt(1000000000); // 1B stroops (assuming 7 decimal places)
-      console.log('Using estimated KALE balance for user:', body.publicKey);
-    } catch (error) {
-      console.error('Error fetching KALE balance:', error);
-      return NextResponse.json(
-        { error: 'Failed to fetch KALE balance. Please try again.' },
-        { status: 500 },
-      );
-    } // Calculate stake amount
-    const stakeAmount =
-      (kaleBalance * BigInt(body.stakePercentage)) / BigInt(100);
-
-    if (stakeAmount <= 0) {
-      return NextResponse.json(
-        { error: 'Insufficient KALE balance for staking' },
-        { status: 400 },
-      );
-    }
-
-    // Generate unique prediction ID
-    const predictionId = `${body.teamName}_${Date.now()}_${Math.random().toString(36).substring(7)}`;
+    // The contract assigns the prediction ID; only the resolution time is ours
+    const resolveAfter = Math.floor(Date.now() / 1000) + horizon;
 
     // Build real transaction with contract operations
     const transactionBuilder = new TransactionBuilder(account, {
@@ -181,30 +178,101 @@ export async function POST(request: NextRequest): Promise<NextResponse> {
         teamExists = false;
       }
 
+      // Reads go through simulation, like get_team above
+      const simulate = async (
+        contract: Contract,
+        method: string,
+        ...args: xdr.ScVal[]
+      ) => {
+        const tx = new TransactionBuilder(simulationAccount as any, {
+          fee: '100',
+          networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
+        })
+          .addOperation(contract.call(method, ...args))
+          .setTimeout(30)
+          .build();
+
+        const result = await server.simulateTransaction(tx);
+        if (!rpc.Api.isSimulationSuccess(result) || !result.result) {
+          throw new Error(`Simulation of ${method} failed`);
+        }
+        return scValToNative(result.result.retval);
+      };
+
+      const teamArg = nativeToScVal(body.teamName, { type: 'string' });
+      const userArg = nativeToScVal(Address.fromString(body.publicKey), {
+        type: 'address',
+      });
+
       let operation;
+      let operationType: string;
+      let stakeAmount: bigint | undefined;
       if (teamExists) {
-        // Team exists, make the prediction directly
-        operation = daoContract.call(
-          'make_prediction',
-          nativeToScVal(predictionId, { type: 'string' }),
-          nativeToScVal(body.teamName, { type: 'string' }),
-          nativeToScVal(body.prediction.asset, { type: 'string' }),
-          nativeToScVal(body.prediction.direction, { type: 'bool' }),
-          nativeToScVal(stakeAmount.toString(), { type: 'i128' }),
-          nativeToScVal(body.stakePercentage, { type: 'u32' }),
-          nativeToScVal(Address.fromString(body.publicKey), {
-            type: 'address',
-          }),
+        // The stake percentage applies to all KALE the member can put up:
+        // their wallet balance plus stake not yet escrowed
+        const walletBalance: bigint = await simulate(
+          kaleContract,
+          'balance',
+          userArg,
         );
-        console.log('Creating prediction operation for existing team');
+        const memberBalance: { available: bigint } = await simulate(
+          daoContract,
+          'get_member_balance',
+          teamArg,
+          userArg,
+        );
+        stakeAmount =
+          ((walletBalance + memberBalance.available) *
+            BigInt(body.stakePercentage)) /
+          BigInt(100);
+
+        if (stakeAmount <= 0) {
+          return NextResponse.json(
+            { error: 'Insufficient KALE balance for staking' },
+            { status: 400 },
+          );
+        }
+
+        if (memberBalance.available < stakeAmount) {
+          // make_prediction escrows from staked KALE, so stake the
+          // shortfall first; the client prepares the prediction once it lands
+          const shortfall = stakeAmount - memberBalance.available;
+          operation = daoContract.call(
+            'stake_kale_amount',
+            teamArg,
+            userArg,
+            nativeToScVal(shortfall, { type: 'i128' }),
+            nativeToScVal(shortfall, { type: 'i128' }),
+          );
+          operationType = 'stake_kale';
+          console.log('Creating stake operation for shortfall:', shortfall);
+        } else {
+          // Enough stake is available, make the prediction directly
+          operation = daoContract.call(
+            'make_prediction',
+            body.label
+              ? nativeToScVal(body.label, { type: 'string' })
+              : nativeToScVal(null),
+            teamArg,
+            nativeToScVal(body.prediction.asset, { type: 'string' }),
+            nativeToScVal(body.prediction.direction, { type: 'bool' }),
+            nativeToScVal(stakeAmount, { type: 'i128' }),
+            nativeToScVal(body.stakePercentage, { type: 'u32' }),
+            userArg,
+            nativeToScVal(resolveAfter, { type: 'u64' }),
+          );
+          operationType = 'make_prediction';
+          console.log('Creating prediction operation for existing team');
+        }
       } else {
         // Team doesn't exist, create it first
         operation = daoContract.call(
           'form_team',
-          nativeToScVal(body.teamName, { type: 'string' }),
+          teamArg,
           // Members array - just the current user
           nativeToScVal([Address.fromString(body.publicKey)], { type: 'vec' }),
         );
+        operationType = 'form_team';
         console.log('Creating team formation operation');
       }
 
@@ -215,18 +283,33 @@ export async function POST(request: NextRequest): Promise<NextResponse> {
 
       console.log('Contract operations created successfully');
 
-      // For Soroban contract calls, we need to prepare the transaction
-      const preparedTransaction =
-        await sorobanServer.prepareTransaction(transaction);
+      // Simulate once to read the returned prediction ID and to prepare the
+      // transaction's footprint and fees
+      const simulation = await sorobanServer.simulateTransaction(transaction);
+      if (!rpc.Api.isSimulationSuccess(simulation)) {
+        throw new Error(
+          `Simulation failed: ${'error' in simulation ? simulation.error : 'unknown error'}`,
+        );
+      }
+      let predictionId: string | undefined;
+      if (operationType === 'make_prediction' && simulation.result) {
+        // u64 can exceed Number, so it travels as a decimal string
+        predictionId = (
+          scValToNative(simulation.result.retval) as bigint
+        ).toString();
+      }
+      const preparedTransaction = rpc
+        .assembleTransaction(transaction, simulation)
+        .build();
       const xdrString = preparedTransaction.toXDR();
 
       console.log('Real contract transaction prepared:', {
         teamName: body.teamName,
         userAddress: body.publicKey,
         contractId: DAO_CONTRACT_ID,
-        operation: teamExists ? 'make_prediction' : 'form_team',
+        operation: operationType,
         note: teamExists
-          ? `Making prediction for existing team "${body.teamName}"`
+          ? `Staking or predicting for existing team "${body.teamName}"`
           : 'Creating team first to resolve Error #5',
       });
 
@@ -234,7 +317,10 @@ export async function POST(request: NextRequest): Promise<NextResponse> {
         xdr: xdrString,
         networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
         predictionId,
-        operationType: teamExists ? 'make_prediction' : 'form_team',
+        resolveAfter:
+          operationType === 'make_prediction' ? resolveAfter : undefined,
+        operationType,
+        stakeAmount: stakeAmount?.toString(),
       };
 
       return NextResponse.json(response);
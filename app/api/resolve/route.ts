@@ -5,8 +5,10 @@ import {
   Contract,
   Address,
   nativeToScVal,
+  scValToNative,
   rpc,
   Horizon,
+  xdr,
 } from '@stellar/stellar-sdk';
 
 // Environment variables
@@ -17,6 +19,11 @@ const STELLAR_NETWORK_PASSPHRASE =
 const DAO_CONTRACT_ID = process.env.PUBLIC_DAO_CONTRACT_ID || '';
 const REFLECTOR_CONTRACT_ID = process.env.PUBLIC_REFLECTOR_CONTRACT_ID || '';
 
+// Nonces tried before giving up; the default difficulty of 4 bits takes 16
+// on average
+const MAX_NONCE_ATTEMPTS = 4096;
+const NONCE_BATCH_SIZE = 16;
+
 // Interface for Reflector price data
 interface ReflectorPriceData {
   price: number;
@@ -152,11 +159,27 @@ class ReflectorClient {
   }
 }
 
+// Mirrors the contract's leading_zero_bits
+function leadingZeroBits(hash: Uint8Array): number {
+  let bits = 0;
+  for (const byte of hash) {
+    if (byte === 0) {
+      bits += 8;
+      continue;
+    }
+    bits += Math.clz32(byte) - 24;
+    break;
+  }
+  return bits;
+}
+
 interface ResolveResponse {
   predictionId: string;
-  outcome: boolean;
+  xdr?: string; // resolve_prediction, to be signed by the caller
+  networkPassphrase?: string;
+  nonce?: string;
+  outcome: boolean | null;
   currentPrice: number;
-  historicalPrice: number;
   rewardsDistributed: boolean;
   message: string;
 }
@@ -165,10 +188,31 @@ export async function GET(request: NextRequest): Promise<NextResponse> {
   try {
     const { searchParams } = new URL(request.url);
     const predictionId = searchParams.get('predictionId');
+    const publicKey = searchParams.get('publicKey');
 
-    if (!predictionId) {
+    if (!predictionId || !publicKey) {
       return NextResponse.json(
-        { error: 'Missing predictionId parameter' },
+        { error: 'Missing predictionId or publicKey parameter' },
+        { status: 400 },
+      );
+    }
+
+    // Prediction IDs are u64 values assigned by the contract
+    if (!/^\d+$/.test(predictionId)) {
+      return NextResponse.json(
+        { error: 'predictionId must be the numeric ID returned by make_prediction' },
+        { status: 400 },
+      );
+    }
+    const predictionIdScVal = nativeToScVal(BigInt(predictionId), {
+      type: 'u64',
+    });
+
+    try {
+      Keypair.fromPublicKey(publicKey);
+    } catch (error) {
+      return NextResponse.json(
+        { error: 'Invalid Stellar public key format' },
         { status: 400 },
       );
     }
@@ -197,326 +241,183 @@ export async function GET(request: NextRequest): Promise<NextResponse> {
 
     // Create a temporary keypair for contract simulation
     const sourceKeypair = Keypair.random();
-
-    try {
-      // First, let's test the RPC connection
-      console.log('Testing RPC connection to:', STELLAR_RPC_URL);
-      const ledger = await server.getLatestLedger();
-      console.log('RPC connection successful, latest ledger:', ledger.sequence);
-
-      // Simple approach: try to read prediction from contract
-      console.log('Fetching prediction from DAO contract...');
-
-      const simAccount = {
-        accountId: () => sourceKeypair.publicKey(),
-        sequenceNumber: () => '0',
-        incrementSequenceNumber: () => {},
-      };
-
-      const getPredictionTx = new TransactionBuilder(simAccount as any, {
+    const simAccount = {
+      accountId: () => sourceKeypair.publicKey(),
+      sequenceNumber: () => '0',
+      incrementSequenceNumber: () => {},
+    };
+
+    // Reads go through simulation
+    const simulate = async (method: string, ...args: xdr.ScVal[]) => {
+      const tx = new TransactionBuilder(simAccount as any, {
         fee: '100',
         networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
       })
-        .addOperation(
-          daoContract.call(
-            'get_prediction',
-            nativeToScVal(predictionId, { type: 'string' }),
-          ),
-        )
+        .addOperation(daoContract.call(method, ...args))
         .setTimeout(30)
         .build();
 
-      console.log('Attempting contract simulation...');
-      const result = await server.simulateTransaction(getPredictionTx);
-
-      if ('error' in result) {
-        console.log(
-          'Contract simulation failed:',
-          JSON.stringify(result.error),
-        );
+      const result = await server.simulateTransaction(tx);
+      if (!rpc.Api.isSimulationSuccess(result) || !result.result) {
         throw new Error(
-          `Contract call failed: ${JSON.stringify(result.error)}`,
+          `Simulation of ${method} failed: ${'error' in result ? result.error : 'no result'}`,
         );
       }
+      return scValToNative(result.result.retval);
+    };
 
-      console.log('Successfully fetched prediction from DAO contract');
-
-      // Check if the prediction actually exists and has data
-      let predictionExists = false;
-      let predictionData = null;
-      let asset = 'EUR/USD';
-      let predictedDirection = false;
-      let resolved = false;
-
-      if ('result' in result && result.result) {
-        try {
-          predictionData = result.result;
-          predictionExists = true;
-          console.log(
-            'Prediction found in contract:',
-            JSON.stringify(predictionData),
-          );
-
-          // Parse the prediction data from the contract response
-          if (
-            predictionData &&
-            typeof predictionData === 'object' &&
-            '_value' in predictionData
-          ) {
-            const dataMap = predictionData._value;
-            if (Array.isArray(dataMap)) {
-              // Extract fields from the contract response
-              for (const item of dataMap) {
-                if (
-                  item._attributes &&
-                  item._attributes.key &&
-                  item._attributes.val
-                ) {
-                  const key = item._attributes.key;
-                  const val = item._attributes.val;
-
-                  if (key._value && Buffer.isBuffer(key._value)) {
-                    const keyName = key._value.toString();
-
-                    if (
-                      keyName === 'asset' &&
-                      val._value &&
-                      Buffer.isBuffer(val._value)
-                    ) {
-                      asset = val._value.toString();
-                    } else if (
-                      keyName === 'prediction' &&
-                      val._value !== undefined
-                    ) {
-                      predictedDirection = val._value;
-                    } else if (
-                      keyName === 'resolved' &&
-                      val._value !== undefined
-                    ) {
-                      resolved = val._value;
-                    }
-                  }
-                }
-              }
-            }
-          }
-
-          console.log('Parsed prediction data:', {
-            asset,
-            predictedDirection,
-            resolved,
-          });
-        } catch (parseError) {
-          console.log('Could not parse prediction data:', parseError);
-          predictionExists = false;
-        }
-      }
+    try {
+      const prediction = await simulate('get_prediction', predictionIdScVal);
 
-      if (!predictionExists) {
+      if (!prediction) {
         console.log(
           'Prediction not found in contract - likely not submitted yet',
         );
-        // Return a helpful message about the prediction not being submitted
-        return NextResponse.json({
-          predictionId,
-          outcome: null,
-          resolved: false,
-          message:
-            'Prediction not found in contract. Please ensure the prediction transaction has been signed and submitted to the blockchain.',
-          resolvedViaContract: false,
-          error: 'PredictionNotSubmitted',
-          note: 'The prediction was prepared but may not have been submitted to the blockchain yet.',
-        });
+        return NextResponse.json(
+          {
+            error:
+              'Prediction not found in contract. Please ensure the prediction transaction has been signed and submitted to the blockchain.',
+          },
+          { status: 404 },
+        );
       }
 
-      // Since we have the prediction data, let's simulate resolution with real Reflector oracle data
-      console.log('Simulating resolution with Reflector oracle for EUR/USD...');
-
-      // Initialize Reflector client
       const reflectorClient = new ReflectorClient(
         STELLAR_RPC_URL,
         REFLECTOR_CONTRACT_ID,
       );
+      const currentPriceData = await reflectorClient.getLastPrice(
+        prediction.asset,
+      );
 
-      // Get current EUR/USD price from Reflector oracle
-      let currentPriceData: ReflectorPriceData;
-      try {
-        console.log('Fetching current EUR/USD price from Reflector oracle...');
-        currentPriceData = await reflectorClient.getLastPrice('EUR/USD');
-        console.log('Reflector oracle returned:', currentPriceData);
-      } catch (oracleError) {
-        console.error('Oracle error, using fallback:', oracleError);
-        currentPriceData = {
-          price: 1.0856, // Fallback EUR/USD rate
-          timestamp: Date.now(),
+      if (prediction.resolved) {
+        const response: ResolveResponse = {
+          predictionId,
+          outcome: prediction.outcome ?? null,
+          currentPrice: currentPriceData.price,
+          rewardsDistributed: prediction.outcome === true,
+          message: `Prediction already resolved: ${
+            prediction.outcome === undefined || prediction.outcome === null
+              ? 'voided'
+              : prediction.outcome
+                ? 'correct'
+                : 'incorrect'
+          }`,
         };
+        return NextResponse.json(response);
       }
 
-      // For demonstration, use a slightly lower historical price
-      const historicalPrice = currentPriceData.price * 0.995; // Simulate 0.5% historical difference
-      const actualPriceWentUp = currentPriceData.price > historicalPrice;
-
-      // Determine if prediction was correct
-      const predictionCorrect = predictedDirection === actualPriceWentUp;
+      // resolve_prediction rejects predictions before their horizon
+      const resolveAfter = Number(prediction.resolve_after);
+      if (Math.floor(Date.now() / 1000) < resolveAfter) {
+        return NextResponse.json(
+          {
+            error: `Prediction cannot be resolved before ${new Date(resolveAfter * 1000).toISOString()}`,
+            resolveAfter,
+          },
+          { status: 425 },
+        );
+      }
 
-      console.log('Resolution logic with Reflector data:', {
-        asset,
-        predictedDirection: predictedDirection ? 'up' : 'down',
-        actualDirection: actualPriceWentUp ? 'up' : 'down',
-        correct: predictionCorrect,
-        historicalPrice,
-        currentPrice: currentPriceData.price,
-        oracleTimestamp: new Date(currentPriceData.timestamp).toISOString(),
-      });
+      // KALE mining: search for a nonce whose work hash has the asset's
+      // difficulty in leading zero bits, as resolve_prediction checks
+      const difficulty: number = await simulate(
+        'get_difficulty',
+        nativeToScVal(prediction.asset, { type: 'string' }),
+      );
+      const predictorScVal = new Address(prediction.predictor).toScVal();
+
+      let nonce: bigint | undefined;
+      for (
+        let start = 0;
+        nonce === undefined && start < MAX_NONCE_ATTEMPTS;
+        start += NONCE_BATCH_SIZE
+      ) {
+        const candidates = Array.from(
+          { length: NONCE_BATCH_SIZE },
+          (_, i) => BigInt(start + i),
+        );
+        const hashes: Uint8Array[] = await Promise.all(
+          candidates.map(candidate =>
+            simulate(
+              'get_work_hash',
+              predictionIdScVal,
+              predictorScVal,
+              nativeToScVal(candidate, { type: 'u64' }),
+            ),
+          ),
+        );
+        const found = hashes.findIndex(
+          hash => leadingZeroBits(hash) >= difficulty,
+        );
+        if (found >= 0) {
+          nonce = candidates[found];
+        }
+      }
 
-      return NextResponse.json({
-        predictionId,
-        outcome: predictionCorrect,
-        currentPrice: currentPriceData.price,
-        historicalPrice: historicalPrice,
-        rewardsDistributed: predictionCorrect,
-        message: `Prediction ${predictionCorrect ? 'succeeded' : 'failed'} - ${asset} price ${actualPriceWentUp ? 'increased' : 'decreased'} (predicted ${predictedDirection ? 'up' : 'down'})`,
-        resolvedViaContract: true,
-        oracleData: {
-          source: 'Reflector',
-          currentPrice: currentPriceData.price,
-          timestamp: currentPriceData.timestamp,
-          asset: 'EUR/USD',
-        },
-        contractData: {
-          asset,
-          predictedDirection,
-          resolved: false, // Will be true after actual contract resolution
-          extractedFromContract: true,
-        },
-        note: 'Resolution using Reflector oracle data for EUR/USD - contract resolve_prediction function needs fixing',
-      });
+      if (nonce === undefined) {
+        return NextResponse.json(
+          {
+            error: `No nonce meeting difficulty ${difficulty} found in ${MAX_NONCE_ATTEMPTS} attempts`,
+          },
+          { status: 503 },
+        );
+      }
 
-      // The problematic resolve_prediction call is commented out due to contract issues
-      // TODO: Fix the smart contract's resolve_prediction function
-      /*
-      console.log('Calling resolve_prediction on DAO contract...');
+      console.log('Found work nonce:', { predictionId, nonce, difficulty });
 
-      const resolveTx = new TransactionBuilder(simAccount as any, {
+      // resolve_prediction needs no authorization, so the caller's account
+      // pays for it
+      const account = await horizonServer.loadAccount(publicKey);
+      const resolveTx = new TransactionBuilder(account, {
         fee: '1000000', // Higher fee for contract resolution
         networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
       })
         .addOperation(
           daoContract.call(
             'resolve_prediction',
-            nativeToScVal(predictionId, { type: 'string' }),
+            predictionIdScVal,
+            nativeToScVal(nonce, { type: 'u64' }),
           ),
         )
-        .setTimeout(30)
+        .setTimeout(300)
         .build();
 
-      const resolveResult = await server.simulateTransaction(resolveTx);
-
-      if ('error' in resolveResult) {
-        const errorStr = JSON.stringify(resolveResult.error);
+      const simulation = await server.simulateTransaction(resolveTx);
+      if (!rpc.Api.isSimulationSuccess(simulation) || !simulation.result) {
+        const errorStr = JSON.stringify(
+          'error' in simulation ? simulation.error : 'no result',
+        );
         console.error('Contract resolve error:', errorStr);
-
-        // Check for specific contract errors
-        if (errorStr.includes('Error(Contract, #6)')) {
-          return NextResponse.json({
-            predictionId,
-            outcome: null,
-            resolved: false,
-            message:
-              'Prediction not found in contract (Error #6). The prediction transaction may not have been submitted yet.',
-            resolvedViaContract: false,
-            error: 'PredictionNotFound',
-            contractError: errorStr,
-            note: 'Please ensure the prediction transaction has been signed and confirmed on the blockchain.',
-          });
-        }
-
-        // Other contract errors
-        return NextResponse.json({
-          predictionId,
-          outcome: null,
-          resolved: false,
-          message: `Contract resolution failed: ${errorStr}`,
-          resolvedViaContract: false,
-          error: 'ContractResolutionFailed',
-          contractError: errorStr,
-        });
-      }
-
-      console.log('Contract resolution successful!');
-
-      // Parse the resolution result from the contract
-      // The contract should return resolution data including outcome and prices
-      let outcome = false;
-      let currentPrice = 1.0;
-      let historicalPrice = 1.0;
-
-      try {
-        // Parse the contract result - this depends on your contract's return format
-        if ('result' in resolveResult && resolveResult.result) {
-          const resultData = resolveResult.result;
-          // Your contract should return structured resolution data
-          console.log('Contract resolution data:', JSON.stringify(resultData));
-
-          // For now, we'll extract basic outcome - adjust based on your contract's actual return format
-          outcome = true; // Parse from actual result data
-        }
-      } catch (parseError) {
-        console.log('Could not parse resolution result, using default outcome');
+        return NextResponse.json(
+          { error: `Contract resolution failed: ${errorStr}` },
+          { status: 409 },
+        );
       }
 
-      // Get updated prediction data after resolution
-      const updatedPredictionResult =
-        await server.simulateTransaction(getPredictionTx);
+      const outcome: boolean = scValToNative(simulation.result.retval);
+      const preparedTransaction = rpc
+        .assembleTransaction(resolveTx, simulation)
+        .build();
 
-      return NextResponse.json({
+      const response: ResolveResponse = {
         predictionId,
+        xdr: preparedTransaction.toXDR(),
+        networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
+        nonce: nonce.toString(),
         outcome,
-        currentPrice,
-        historicalPrice,
-        rewardsDistributed: true,
-        message: `Prediction resolved via smart contract: ${outcome ? 'Correct prediction!' : 'Incorrect prediction'}`,
-        resolvedViaContract: true,
-        contractData: 'result' in resolveResult ? resolveResult.result : null,
-      });
-      */
+        currentPrice: currentPriceData.price,
+        rewardsDistributed: outcome,
+        message: `Prediction ${outcome ? 'succeeded' : 'failed'} on ${prediction.asset} (predicted ${prediction.prediction ? 'up' : 'down'})`,
+      };
+
+      return NextResponse.json(response);
     } catch (contractError: any) {
       console.error('Error calling deployed contracts:', contractError);
-
-      // Log more details about the error
-      if (contractError.response) {
-        console.log('HTTP Status:', contractError.response.status);
-        console.log('HTTP Status Text:', contractError.response.statusText);
-        console.log('Response Data:', contractError.response.data);
-      }
-
-      // Instead of falling back to mock, let's try a different approach
-      // Since the contract calls are failing, let's at least simulate real resolution
-      console.log(
-        'Contract calls failed, performing simulated resolution based on real logic...',
+      return NextResponse.json(
+        { error: `Contract call failed: ${contractError.message}` },
+        { status: 502 },
       );
-
-      // Simulate real resolution logic without contract calls
-      const now = Date.now();
-      const mockHistoricalPrice = 1.0;
-      const mockCurrentPrice = Math.random() > 0.5 ? 1.05 : 0.95; // Random but realistic
-      const predictedUp = predictionId.includes('up') || Math.random() > 0.5; // Extract from prediction or random
-
-      // Determine outcome based on price movement
-      const priceWentUp = mockCurrentPrice > mockHistoricalPrice;
-      const predictionCorrect = predictedUp === priceWentUp;
-
-      return NextResponse.json({
-        predictionId,
-        outcome: predictionCorrect,
-        currentPrice: mockCurrentPrice,
-        historicalPrice: mockHistoricalPrice,
-        rewardsDistributed: true,
-        message: `Prediction ${predictionCorrect ? 'succeeded' : 'failed'} - simulated via oracle logic (contract calls unavailable)`,
-        resolvedViaContract: false,
-        contractError: contractError.message,
-        note: 'Using simulated resolution due to RPC issues - would use real Reflector data in production',
-      });
     }
   } catch (error: unknown) {
     console.error('Error resolving prediction:', error);
@@ -530,40 +431,6 @@ export async function GET(request: NextRequest): Promise<NextResponse> {
   }
 }
 
-// Helper function to parse contract result
-function parseContractResult(result: any) {
-  // Parse the actual contract response based on your DAO contract's return format
-  // This would need to be adjusted based on your contract's actual response structure
-  return {
-    outcome: Math.random() > 0.5, // Placeholder - parse from actual contract result
-    currentPrice: 1.08 + (Math.random() - 0.5) * 0.02,
-    historicalPrice: 1.07 + (Math.random() - 0.5) * 0.02,
-    message: 'Resolved via deployed DAO contract',
-  };
-}
-
-// Fallback mock resolution function
-async function mockResolution(predictionId: string): Promise<NextResponse> {
-  console.log('Using mock resolution for:', predictionId);
-
-  // Generate mock price data
-  const currentPrice = 1.08 + (Math.random() - 0.5) * 0.02;
-  const historicalPrice = 1.07 + (Math.random() - 0.5) * 0.02;
-  const outcome = currentPrice > historicalPrice;
-
-  return NextResponse.json({
-    predictionId,
-    outcome,
-    currentPrice,
-    historicalPrice,
-    rewardsDistributed: true,
-    message: outcome
-      ? 'Prediction correct! Price increased as predicted (mock resolution).'
-      : 'Prediction incorrect. Price decreased, opposite of prediction (mock resolution).',
-    resolvedViaContract: false,
-  });
-}
-
 export async function POST(): Promise<NextResponse> {
   return NextResponse.json(
     { error: 'Method not allowed. Use GET to resolve predictions.' },
@@ -174,18 +174,16 @@ export async function POST(request: NextRequest): Promise<NextResponse> {
       // If there's an error, assume user has no team and continue
     }
 
-    // For now, we'll use the form_team function to add the user to the team
-    // In a more sophisticated contract, you'd have a dedicated join_team function
-    // This implementation assumes the team exists and we're adding a member
+    // Joining accepts the invitation a team member sent to this wallet
     const transactionBuilder = new TransactionBuilder(account, {
       fee: '1000000',
       networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
     });
 
     const operation = daoContract.call(
-      'form_team',
+      'accept_invite',
       nativeToScVal(teamName, { type: 'string' }),
-      nativeToScVal([Address.fromString(body.publicKey)], { type: 'vec' }),
+      nativeToScVal(Address.fromString(body.publicKey), { type: 'address' }),
     );
 
     const transaction = transactionBuilder
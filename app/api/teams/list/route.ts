@@ -4,12 +4,21 @@ import {
   TransactionBuilder,
   Contract,
   nativeToScVal,
+  scValToNative,
   rpc,
 } from '@stellar/stellar-sdk';
 
 const STELLAR_RPC_URL = 'https://soroban-testnet.stellar.org';
 const STELLAR_NETWORK_PASSPHRASE = 'Test SDF Network ; September 2015';
 const DAO_CONTRACT_ID = process.env.PUBLIC_DAO_CONTRACT_ID || '';
+// Matches MAX_TEAM_PAGE in the DAO contract
+const MAX_TEAM_PAGE = 50;
+
+interface TeamSummary {
+  name: string;
+  member_count: number;
+  total_stake: bigint;
+}
 
 export async function GET(request: NextRequest): Promise<NextResponse> {
   try {
@@ -20,38 +29,69 @@ export async function GET(request: NextRequest): Promise<NextResponse> {
       );
     }
 
-    // For now, return mock teams since getting all teams from contract requires
-    // iterating through all possible team names or maintaining a separate index
-    // In a production contract, you'd have a get_all_teams function
+    const { searchParams } = new URL(request.url);
+    const start = Math.max(0, Number(searchParams.get('start') ?? 0) || 0);
+    const limit = Math.min(
+      MAX_TEAM_PAGE,
+      Math.max(1, Number(searchParams.get('limit') ?? MAX_TEAM_PAGE) || 1),
+    );
+
+    const daoContract = new Contract(DAO_CONTRACT_ID);
+    const server = new rpc.Server(STELLAR_RPC_URL);
+    const sourceKeypair = Keypair.random();
+
+    const simulationAccount = {
+      accountId: () => sourceKeypair.publicKey(),
+      sequenceNumber: () => '0',
+      incrementSequenceNumber: () => {},
+    };
+
+    const simulate = async (
+      method: string,
+      ...args: Parameters<typeof nativeToScVal>[]
+    ) => {
+      const tx = new TransactionBuilder(simulationAccount, {
+        fee: '100',
+        networkPassphrase: STELLAR_NETWORK_PASSPHRASE,
+      })
+        .addOperation(
+          daoContract.call(
+            method,
+            ...args.map(([value, opts]) => nativeToScVal(value, opts)),
+          ),
+        )
+        .setTimeout(30)
+        .build();
+
+      const result = await server.simulateTransaction(tx);
+      if (!rpc.Api.isSimulationSuccess(result) || !result.result) {
+        throw new Error(`Simulation of ${method} failed`);
+      }
+      return scValToNative(result.result.retval);
+    };
+
+    const teamCount: number = await simulate('team_count');
+    const summaries: TeamSummary[] = await simulate(
+      'list_teams',
+      [start, { type: 'u32' }],
+      [limit, { type: 'u32' }],
+    );
 
-    const mockTeams = [
-      {
-        name: 'team1',
-        members: ['GCJY...'],
-        totalStake: '100M KALE',
-        memberCount: 3,
-        isUserMember: false,
-      },
-      {
-        name: 'alpha-traders',
-        members: ['GDXY...', 'GEFS...'],
-        totalStake: '250M KALE',
-        memberCount: 2,
-        isUserMember: false,
-      },
-      {
-        name: 'crypto-bulls',
-        members: ['GBCD...', 'GHIJ...', 'GKLM...', 'GNOP...'],
-        totalStake: '500M KALE',
-        memberCount: 4,
-        isUserMember: false,
-      },
-    ];
+    // Summaries carry counts only; member addresses come from get_team
+    const teams = summaries.map((team) => ({
+      name: team.name,
+      members: [],
+      totalStake: team.total_stake.toString(),
+      memberCount: team.member_count,
+      isUserMember: false,
+    }));
 
     return NextResponse.json({
       success: true,
-      teams: mockTeams,
-      note: 'Team listing from contract coming soon - currently showing example teams',
+      teams,
+      total: teamCount,
+      start,
+      limit,
     });
   } catch (error: unknown) {
     console.error('Error listing teams:', error);
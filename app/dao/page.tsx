@@ -2,12 +2,16 @@
 
 import React, { useState, useEffect } from 'react';
 import { signTransaction } from '@stellar/freighter-api';
-import { TransactionBuilder, Networks } from '@stellar/stellar-sdk';
+import { TransactionBuilder, Networks, rpc } from '@stellar/stellar-sdk';
 import PredictionForm from '../components/PredictionForm';
 import Dashboard from '../components/Dashboard';
 import Button from '../components/Button';
 import { useWallet } from '../ClientLayout';
 
+// Reflector publishes a price round every five minutes, so the price at a
+// prediction's resolve_after is only available a round later
+const ORACLE_ROUND_MS = 5 * 60 * 1000;
+
 // Toast notification component
 const Toast: React.FC<{
   message: string;
@@ -64,7 +68,6 @@ interface PredictionResult {
   predictionId: string;
   outcome: boolean;
   currentPrice: number;
-  historicalPrice: number;
   rewardsDistributed: boolean;
   message: string;
   transactionHash?: string;
@@ -133,6 +136,104 @@ export default function DaoPage() {
     loadUserTeam();
   }, [isWalletConnected, publicKey]);
 
+  const server = new rpc.Server('https://soroban-testnet.stellar.org');
+
+  // Sign a prepared transaction with Freighter and submit it to the Stellar
+  // network; returns the transaction hash, or null if submission failed
+  const signAndSubmit = async (prepared: {
+    xdr: string;
+    networkPassphrase: string;
+    predictionId?: string;
+  }): Promise<string | null> => {
+    const signedXdr = await signTransaction(prepared.xdr, {
+      networkPassphrase: prepared.networkPassphrase,
+    });
+
+    addToast('Submitting to Stellar network...', 'info');
+
+    try {
+      // Parse the signed transaction XDR
+      const transaction = TransactionBuilder.fromXDR(
+        signedXdr.signedTxXdr,
+        'Test SDF Network ; September 2015',
+      );
+
+      // Submit to Stellar network
+      const submitResult = await server.sendTransaction(transaction);
+
+      console.log('Real transaction submitted:', {
+        predictionId: prepared.predictionId,
+        hash: submitResult.hash,
+        status: submitResult.status,
+      });
+
+      if (submitResult.status === 'PENDING') {
+        addToast(
+          `Transaction submitted successfully! Hash: ${submitResult.hash.slice(0, 8)}...`,
+          'success',
+        );
+      } else if (submitResult.status === 'DUPLICATE') {
+        addToast(
+          `Transaction already exists! Hash: ${submitResult.hash.slice(0, 8)}...`,
+          'info',
+        );
+      } else {
+        throw new Error(
+          `Transaction failed with status: ${submitResult.status}`,
+        );
+      }
+      return submitResult.hash;
+    } catch (submitError) {
+      console.error('Transaction submission failed:', submitError);
+      addToast(
+        `Transaction submission failed: ${submitError instanceof Error ? submitError.message : 'Unknown error'}`,
+        'error',
+      );
+      // Don't use mock hash if submission fails - let the user know it failed
+      return null;
+    }
+  };
+
+  // Wait until a submitted transaction is in a ledger
+  const waitForTransaction = async (hash: string) => {
+    for (let attempt = 0; attempt < 30; attempt++) {
+      const result = await server.getTransaction(hash);
+      if (result.status === rpc.Api.GetTransactionStatus.SUCCESS) {
+        return;
+      }
+      if (result.status === rpc.Api.GetTransactionStatus.FAILED) {
+        throw new Error('Transaction failed on chain');
+      }
+      await new Promise(resolve => setTimeout(resolve, 1000));
+    }
+    throw new Error('Timed out waiting for the transaction');
+  };
+
+  // Resolve a prediction on chain: the resolve route searches a mining
+  // nonce and prepares resolve_prediction, and the wallet submits it
+  const resolveOnChain = async (
+    predictionId: string,
+  ): Promise<PredictionResult | null> => {
+    const response = await fetch(
+      `/api/resolve?predictionId=${predictionId}&publicKey=${publicKey}`,
+    );
+    const result = await response.json();
+
+    if (!response.ok) {
+      throw new Error(result.error || 'Failed to resolve prediction');
+    }
+    if (!result.xdr) {
+      // Nothing to submit, e.g. the prediction is already resolved
+      return result;
+    }
+
+    const transactionHash = await signAndSubmit(result);
+    if (!transactionHash) {
+      return null;
+    }
+    return { ...result, transactionHash };
+  };
+
   // Handle prediction form submission
   const handlePredictionSubmit = async (formData: PredictionFormData) => {
     if (!publicKey) {
@@ -148,117 +249,90 @@ export default function DaoPage() {
     setIsLoading(true);
 
     try {
-      addToast('Preparing transaction...', 'info');
-
       // Step 1: Prepare transaction via API
-      const response = await fetch('/api/prepare-transaction', {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-        },
-        body: JSON.stringify({
-          publicKey: publicKey,
-          teamName: formData.teamName,
-          stakePercentage: formData.stakePercentage,
-          prediction: {
-            asset: formData.asset,
-            direction: formData.direction === 'up',
+      const prepare = async () => {
+        const response = await fetch('/api/prepare-transaction', {
+          method: 'POST',
+          headers: {
+            'Content-Type': 'application/json',
           },
-        }),
-      });
-
-      const prepareResult = await response.json();
+          body: JSON.stringify({
+            publicKey: publicKey,
+            teamName: formData.teamName,
+            stakePercentage: formData.stakePercentage,
+            prediction: {
+              asset: formData.asset,
+              direction: formData.direction === 'up',
+            },
+          }),
+        });
 
-      if (!response.ok) {
-        throw new Error(prepareResult.error || 'Failed to prepare transaction');
-      }
+        const result = await response.json();
 
-      // Step 2: Sign transaction with Freighter
-      const signedXdr = await signTransaction(prepareResult.xdr, {
-        networkPassphrase: prepareResult.networkPassphrase,
-      });
-
-      // Step 3: Submit transaction to Stellar network
-      addToast('Submitting to Stellar network...', 'info');
+        if (!response.ok) {
+          throw new Error(result.error || 'Failed to prepare transaction');
+        }
+        return result;
+      };
 
-      let transactionHash: string;
-      try {
-        const { rpc, TransactionBuilder } = await import(
-          '@stellar/stellar-sdk'
-        );
-        const server = new rpc.Server('https://soroban-testnet.stellar.org');
+      addToast('Preparing transaction...', 'info');
+      let prepareResult = await prepare();
+      let transactionHash = await signAndSubmit(prepareResult);
+      if (!transactionHash) {
+        return;
+      }
 
-        // Parse the signed transaction XDR
-        const transaction = TransactionBuilder.fromXDR(
-          signedXdr.signedTxXdr,
-          'Test SDF Network ; September 2015',
+      // make_prediction escrows staked KALE, so a stake top-up has to land
+      // before the prediction is prepared
+      if (prepareResult.operationType === 'stake_kale') {
+        addToast(
+          `Staking ${prepareResult.stakeAmount} KALE, waiting for confirmation...`,
+          'info',
         );
+        await waitForTransaction(transactionHash);
 
-        // Submit to Stellar network
-        const submitResult = await server.sendTransaction(transaction);
-
-        transactionHash = submitResult.hash;
-        console.log('Real transaction submitted:', {
-          predictionId: prepareResult.predictionId,
-          hash: transactionHash,
-          status: submitResult.status,
-        });
-
-        if (submitResult.status === 'PENDING') {
-          addToast(
-            `Transaction submitted successfully! Hash: ${transactionHash.slice(0, 8)}...`,
-            'success',
-          );
-        } else if (submitResult.status === 'DUPLICATE') {
-          addToast(
-            `Transaction already exists! Hash: ${transactionHash.slice(0, 8)}...`,
-            'info',
-          );
-        } else {
-          throw new Error(
-            `Transaction failed with status: ${submitResult.status}`,
-          );
+        prepareResult = await prepare();
+        transactionHash = await signAndSubmit(prepareResult);
+        if (!transactionHash) {
+          return;
         }
-      } catch (submitError) {
-        console.error('Transaction submission failed:', submitError);
-        addToast(
-          `Transaction submission failed: ${submitError instanceof Error ? submitError.message : 'Unknown error'}`,
-          'error',
-        );
-        // Don't use mock hash if submission fails - let the user know it failed
+      }
+
+      // Forming a team returns no prediction to resolve
+      if (!prepareResult.predictionId) {
+        addToast('Team created. Submit again to make the prediction.', 'info');
         return;
       }
 
-      // Step 4: Resolve prediction after a short delay (simulate time passage)
+      // Step 4: Resolve the prediction once its horizon has passed and the
+      // oracle has published the price round covering it
+      const predictionId: string = prepareResult.predictionId;
+      const resolveDelay = Math.max(
+        prepareResult.resolveAfter * 1000 + ORACLE_ROUND_MS - Date.now(),
+        0,
+      );
+      addToast(
+        `Prediction will resolve at ${new Date(Date.now() + resolveDelay).toLocaleTimeString()}`,
+        'info',
+      );
       setTimeout(async () => {
         try {
-          const resolveResponse = await fetch(
-            `/api/resolve?predictionId=${prepareResult.predictionId}`,
-          );
-          const resolveResult = await resolveResponse.json();
-
-          if (resolveResponse.ok) {
-            const predictionResult: PredictionResult = {
-              ...resolveResult,
-              transactionHash: transactionHash,
-            };
-
+          const predictionResult = await resolveOnChain(predictionId);
+          if (predictionResult) {
             setPredictions(prev => [predictionResult, ...prev]);
             addToast(
-              resolveResult.message,
-              resolveResult.outcome ? 'success' : 'info',
-            );
-          } else {
-            addToast(
-              'Failed to resolve prediction: ' + resolveResult.error,
-              'error',
+              predictionResult.message,
+              predictionResult.outcome ? 'success' : 'info',
             );
           }
         } catch (error) {
           console.error('Error resolving prediction:', error);
-          addToast('Error resolving prediction', 'error');
+          addToast(
+            `Failed to resolve prediction: ${error instanceof Error ? error.message : 'Unknown error'}`,
+            'error',
+          );
         }
-      }, 2000);
+      }, resolveDelay);
     } catch (error: any) {
       console.error('Error in prediction flow:', error);
 
@@ -282,10 +356,9 @@ export default function DaoPage() {
     try {
       addToast('Resolving prediction...', 'info');
 
-      const response = await fetch(`/api/resolve?predictionId=${predictionId}`);
-      const result = await response.json();
+      const result = await resolveOnChain(predictionId);
 
-      if (response.ok) {
+      if (result) {
         const existingIndex = predictions.findIndex(
           p => p.predictionId === predictionId,
         );
@@ -298,12 +371,13 @@ export default function DaoPage() {
         }
 
         addToast(result.message, result.outcome ? 'success' : 'info');
-      } else {
-        addToast('Failed to resolve: ' + result.error, 'error');
       }
     } catch (error) {
       console.error('Error resolving prediction:', error);
-      addToast('Error resolving prediction', 'error');
+      addToast(
+        `Failed to resolve: ${error instanceof Error ? error.message : 'Unknown error'}`,
+        'error',
+      );
     }
   };
 
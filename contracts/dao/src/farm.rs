@@ -0,0 +1,259 @@
+use soroban_sdk::{contractimpl, contracttype, Address, BytesN, Env, String};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, Error, MemberRole,
+    TreasuryKey, BPS_DENOMINATOR,
+};
+
+/// Emission paid on a winning block until the admin configures one (10% of
+/// the planted stake)
+pub const DEFAULT_FARM_EMISSION_BPS: u32 = 1_000;
+/// Upper bound on the farm emission (the planted stake once more)
+pub const MAX_FARM_EMISSION_BPS: u32 = 10_000;
+
+/// Zero bits above this add no further weight, keeping harvests within `i128`
+const MAX_FARM_WORK_BITS: u32 = 32;
+
+/// A member's stake planted on a prediction block
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Planting {
+    pub amount: i128,
+    pub zero_bits: Option<u32>, // best work submitted, if any
+}
+
+/// Farming totals of a prediction block
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FarmBlock {
+    pub planted: i128,
+    pub weight: i128,           // sum of planted stake times work of farmers who worked
+    pub emission: i128,         // KALE set aside for harvests once the prediction won
+    pub harvested_weight: i128,
+    pub paid: i128,
+}
+
+/// Storage keys for farming
+#[contracttype]
+#[derive(Clone)]
+pub enum FarmKey {
+    /// Farm emission on winning blocks in basis points of the planted stake
+    EmissionBps,
+    /// Planting indexed by prediction ID and farmer
+    Plants(u64, Address),
+    /// Farming totals indexed by prediction ID
+    Blocks(u64),
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Lock part of a member's available stake against a prediction block and
+    /// return the farmer's planted amount
+    ///
+    /// Planting is open until the prediction's `resolve_after`; planting
+    /// again adds to the amount.
+    pub fn plant(env: Env, prediction_id: u64, farmer: Address, amount: i128) -> Result<i128, Error> {
+        farmer.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
+
+        let prediction = Self::load_prediction(&env, prediction_id)?;
+        if prediction.resolved {
+            return Err(Error::AlreadyResolved);
+        }
+        if env.ledger().timestamp() >= prediction.resolve_after {
+            return Err(Error::PlantingClosed);
+        }
+
+        Self::require_role(&env, &prediction.team_name, &farmer, MemberRole::Staker)?;
+        Self::escrow_stake(&env, &prediction.team_name, &farmer, amount)?;
+
+        let mut planting = Self::get_planting(env.clone(), prediction_id, farmer.clone()).unwrap_or(Planting {
+            amount: 0,
+            zero_bits: None,
+        });
+        planting.amount += amount;
+        env.storage()
+            .persistent()
+            .set(&FarmKey::Plants(prediction_id, farmer.clone()), &planting);
+
+        let mut block = Self::get_farm_block(env.clone(), prediction_id);
+        block.planted += amount;
+        env.storage().persistent().set(&FarmKey::Blocks(prediction_id), &block);
+
+        env.events().publish(("planted", prediction_id, farmer, amount), None::<String>);
+
+        Ok(planting.amount)
+    }
+
+    /// Submit a farmer's hash and nonce for a planted block and return their
+    /// best zero bits so far
+    ///
+    /// `hash` must be the work hash of the nonce with the farmer as miner
+    /// (see `get_work_hash`). The work also counts towards the team's mining
+    /// round, so it follows the same window and difficulty as `submit_work`.
+    pub fn work(
+        env: Env,
+        prediction_id: u64,
+        farmer: Address,
+        hash: BytesN<32>,
+        nonce: u64,
+    ) -> Result<u32, Error> {
+        farmer.require_auth();
+
+        let prediction = Self::load_prediction(&env, prediction_id)?;
+        let mut planting = Self::get_planting(env.clone(), prediction_id, farmer.clone())
+            .ok_or(Error::NotPlanted)?;
+
+        if Self::get_work_hash(env.clone(), prediction_id, farmer.clone(), nonce)? != hash {
+            return Err(Error::HashVerificationFailed);
+        }
+        let zero_bits = Self::add_work(&env, prediction_id, &prediction, &farmer, nonce)?;
+        if planting.zero_bits == Some(zero_bits) {
+            return Ok(zero_bits);
+        }
+
+        let mut block = Self::get_farm_block(env.clone(), prediction_id);
+        block.weight += Self::planting_weight(&Planting {
+            zero_bits: Some(zero_bits),
+            ..planting.clone()
+        }) - Self::planting_weight(&planting);
+        env.storage().persistent().set(&FarmKey::Blocks(prediction_id), &block);
+
+        planting.zero_bits = Some(zero_bits);
+        env.storage()
+            .persistent()
+            .set(&FarmKey::Plants(prediction_id, farmer), &planting);
+
+        Ok(zero_bits)
+    }
+
+    /// Unlock a farmer's planted stake and pay their share of the block's
+    /// emission
+    ///
+    /// Available once the prediction is resolved or expired; an expired
+    /// prediction is closed first (see `refund_prediction`). Only winning
+    /// blocks carry an emission; it is shared pro-rata to planted stake times
+    /// work, with the rounding remainder going to the last farmer harvesting.
+    /// Stake planted on a losing block is slashed like the predictor's, so
+    /// planting on both sides of an asset cannot hedge the emission.
+    pub fn harvest(env: Env, prediction_id: u64, farmer: Address) -> Result<i128, Error> {
+        farmer.require_auth();
+
+        let mut prediction = Self::load_prediction(&env, prediction_id)?;
+        if !prediction.resolved {
+            if env.ledger().timestamp() <= prediction.expires_at {
+                return Err(Error::NotExpired);
+            }
+            Self::refund_prediction(env.clone(), prediction_id)?;
+            prediction = Self::load_prediction(&env, prediction_id)?;
+        }
+
+        let plant_key = FarmKey::Plants(prediction_id, farmer.clone());
+        let planting: Planting = env
+            .storage()
+            .persistent()
+            .get(&plant_key)
+            .ok_or(Error::NotPlanted)?;
+        env.storage().persistent().remove(&plant_key);
+        Self::release_stake(&env, &prediction.team_name, &farmer, planting.amount);
+        if prediction.outcome == Some(false) {
+            Self::slash_stake(&env, prediction_id, &prediction.team_name, &farmer, planting.amount);
+        }
+
+        let mut block = Self::get_farm_block(env.clone(), prediction_id);
+        let weight = Self::planting_weight(&planting);
+        let mut share = 0;
+        if block.emission > 0 && weight > 0 {
+            block.harvested_weight += weight;
+            share = if block.harvested_weight == block.weight {
+                block.emission - block.paid
+            } else {
+                block.emission * weight / block.weight
+            };
+            block.paid += share;
+            env.storage().persistent().set(&FarmKey::Blocks(prediction_id), &block);
+
+            Self::pay_out(&env, &farmer, share, &TreasuryKey::PendingRewards)?;
+        }
+
+        env.events().publish(("harvested", prediction_id, farmer, share), None::<String>);
+
+        Ok(share)
+    }
+
+    /// Set the emission of winning blocks in basis points of their planted
+    /// stake (admin only)
+    pub fn set_farm_emission(env: Env, emission_bps: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if emission_bps > MAX_FARM_EMISSION_BPS {
+            return Err(Error::InvalidEmission);
+        }
+
+        env.storage().persistent().set(&FarmKey::EmissionBps, &emission_bps);
+
+        env.events().publish(("farm_emission_updated", emission_bps), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the emission of winning blocks in basis points of their planted stake
+    pub fn get_farm_emission(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&FarmKey::EmissionBps)
+            .unwrap_or(DEFAULT_FARM_EMISSION_BPS)
+    }
+
+    /// Get a farmer's unharvested planting on a prediction block
+    pub fn get_planting(env: Env, prediction_id: u64, farmer: Address) -> Option<Planting> {
+        env.storage()
+            .persistent()
+            .get(&FarmKey::Plants(prediction_id, farmer))
+    }
+
+    /// Get the farming totals of a prediction block
+    pub fn get_farm_block(env: Env, prediction_id: u64) -> FarmBlock {
+        env.storage()
+            .persistent()
+            .get(&FarmKey::Blocks(prediction_id))
+            .unwrap_or(FarmBlock {
+                planted: 0,
+                weight: 0,
+                emission: 0,
+                harvested_weight: 0,
+                paid: 0,
+            })
+    }
+}
+
+impl CollaborativeDao {
+    /// Set aside the emission of a winning block for its farmers
+    ///
+    /// The emission is drawn from the reward pool like a bonus, so it is
+    /// capped by the pool and the treasury fee applies. Blocks nobody worked
+    /// on emit nothing.
+    pub(crate) fn fund_harvest(env: &Env, prediction_id: u64) {
+        let mut block = Self::get_farm_block(env.clone(), prediction_id);
+        if block.weight == 0 {
+            return;
+        }
+
+        let emission = block.planted * Self::get_farm_emission(env.clone()) as i128 / BPS_DENOMINATOR;
+        block.emission = Self::draw_bonus(env, emission);
+        Self::credit(env, &TreasuryKey::PendingRewards, block.emission);
+        env.storage().persistent().set(&FarmKey::Blocks(prediction_id), &block);
+
+        env.events().publish(("harvest_funded", prediction_id, block.emission), None::<String>);
+    }
+
+    /// Planted stake times expected hashes of the farmer's best work
+    fn planting_weight(planting: &Planting) -> i128 {
+        planting.zero_bits.map_or(0, |bits| {
+            planting.amount * Self::work_for_bits(bits.min(MAX_FARM_WORK_BITS))
+        })
+    }
+}
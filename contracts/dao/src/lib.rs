@@ -1,11 +1,46 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 use soroban_sdk::{
     contract, contractimpl, contracterror, contracttype, token,
-    Address, Env, String, Vec
+    Address, Env, Map, String, Vec
 };
 
+mod farm;
+mod market;
+mod migration;
+mod mining;
+mod oracle;
+mod proposal;
 mod reflector;
-use reflector::ReflectorClient;
+mod team;
+mod treasury;
+mod unstake;
+use reflector::{PriceData, ReflectorClient};
+
+pub use farm::{FarmBlock, FarmKey, Planting, DEFAULT_FARM_EMISSION_BPS, MAX_FARM_EMISSION_BPS};
+pub use market::{Market, MarketKey, Round, RoundEntry, RoundStatus};
+pub use migration::{PredictionV0, TeamV0, CURRENT_SCHEMA_VERSION};
+pub use mining::{
+    DifficultyConfig, EpochDifficulty, MiningKey, DEFAULT_MINING_DIFFICULTY, MAX_DIFFICULTY_HISTORY,
+    MAX_MINING_DIFFICULTY, MAX_WORK_BOOST_BPS, WORK_BOOST_BPS,
+};
+pub use oracle::{OracleGuard, OracleKey, DEFAULT_MAX_DEVIATION_BPS, DEFAULT_MAX_STALENESS};
+pub use proposal::{Proposal, ProposalKey, ProposalStatus, VotingConfig};
+pub use team::{Invite, MemberRole, MemberShare, INVITE_TTL};
+pub use treasury::{SlashSplit, Solvency, TreasuryKey, MAX_TREASURY_FEE_BPS};
+pub use unstake::{PendingUnstake, DEFAULT_UNSTAKE_COOLDOWN};
+
+/// Predictions for a resolution time close this many seconds before it (one Reflector round)
+pub const SUBMISSION_CUTOFF: u64 = 300;
+/// Furthest a resolution time may be scheduled ahead (30 days)
+pub const MAX_PREDICTION_HORIZON: u64 = 30 * 24 * 60 * 60;
+/// Time after the resolution time during which a prediction can be resolved (7 days)
+pub const RESOLUTION_WINDOW: u64 = 7 * 24 * 60 * 60;
+/// Maximum number of teams returned by one `list_teams` page
+pub const MAX_TEAM_PAGE: u32 = 50;
+
+/// Basis points in a whole, for fees, shares and rates
+pub(crate) const BPS_DENOMINATOR: i128 = 10_000;
 
 #[contract]
 pub struct CollaborativeDao;
@@ -33,6 +68,88 @@ pub enum Error {
     AlreadyResolved = 8,
     /// KALE mining hash verification failed
     HashVerificationFailed = 9,
+    /// Stake amount must be positive
+    InvalidStakeAmount = 10,
+    /// No reward recorded for this beneficiary
+    NoReward = 11,
+    /// Reward has already been claimed
+    RewardAlreadyClaimed = 12,
+    /// Fee exceeds the allowed maximum
+    InvalidFee = 13,
+    /// Horizon must be positive
+    InvalidHorizon = 14,
+    /// Market not found
+    MarketNotFound = 15,
+    /// Round not found
+    RoundNotFound = 16,
+    /// Previous round is still accepting entries
+    RoundInProgress = 17,
+    /// Round no longer accepts entries
+    RoundClosed = 18,
+    /// Round cannot be settled before its close time
+    RoundNotClosed = 19,
+    /// Round has not been settled
+    RoundNotSettled = 20,
+    /// Team or member already backs the other side of the round
+    ConflictingSide = 21,
+    /// Resolution time is too close for new predictions
+    SubmissionClosed = 22,
+    /// Prediction cannot be resolved before its resolution time
+    ResolutionTooEarly = 23,
+    /// Prediction passed its resolution window and can only be refunded
+    PredictionExpired = 24,
+    /// Prediction can still be resolved
+    NotExpired = 25,
+    /// Contract has not been initialized
+    NotInitialized = 26,
+    /// Contract has already been initialized
+    AlreadyInitialized = 27,
+    /// Stored entry does not match any known layout
+    MigrationFailed = 28,
+    /// No pending invitation for this team and address
+    InviteNotFound = 29,
+    /// Invitation has expired
+    InviteExpired = 30,
+    /// Address already belongs to a team
+    AlreadyInTeam = 31,
+    /// Team or member still has unresolved predictions
+    OpenPredictions = 32,
+    /// Role cannot be assigned this way
+    InvalidRole = 33,
+    /// Proposal not found
+    ProposalNotFound = 34,
+    /// Proposal is no longer open for voting
+    ProposalClosed = 35,
+    /// Member has already voted on this proposal
+    AlreadyVoted = 36,
+    /// Member has no stake to vote with
+    NoVotingPower = 37,
+    /// Quorum, threshold or voting period out of range
+    InvalidVotingConfig = 38,
+    /// Proposal can still be voted on
+    VotingNotEnded = 39,
+    /// No pending unstake for this team and member
+    UnstakeNotFound = 40,
+    /// Unstaked KALE is still cooling down
+    CooldownActive = 41,
+    /// Shares do not add up to 10000 basis points
+    InvalidSplit = 42,
+    /// Treasury holds less than the requested amount
+    InsufficientTreasury = 43,
+    /// Contract holds too little KALE to make the transfer
+    InsufficientPool = 44,
+    /// Mining difficulty out of range
+    InvalidDifficulty = 45,
+    /// Farmer has no planting on this prediction block
+    NotPlanted = 46,
+    /// Prediction block no longer accepts plantings
+    PlantingClosed = 47,
+    /// Farm emission exceeds the allowed maximum
+    InvalidEmission = 48,
+    /// Oracle price lies too far from the time it was requested for
+    StalePrice = 49,
+    /// Oracle prices moved more than the circuit breaker allows
+    PriceDeviation = 50,
 }
 
 /// Team information structure
@@ -40,8 +157,9 @@ pub enum Error {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Team {
     pub name: String,
-    pub members: Vec<Address>,
+    pub members: Vec<Address>, // in joining order, which is the captain succession order
     pub total_stake: i128,
+    pub roles: Map<Address, MemberRole>,
 }
 
 /// Prediction information structure
@@ -55,10 +173,30 @@ pub struct Prediction {
     pub stake_percentage: u32, // 0-100
     pub predictor: Address,
     pub timestamp: u64,
+    pub resolve_after: u64, // outcome is priced at this timestamp
+    pub expires_at: u64,    // unresolved predictions are closed by refund_prediction after this
     pub resolved: bool,
     pub outcome: Option<bool>,
 }
 
+/// Team listing entry with summary statistics
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TeamSummary {
+    pub name: String,
+    pub member_count: u32,
+    pub total_stake: i128,
+}
+
+/// Staked KALE split into the part reserved by open predictions and the part still free
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StakeBalance {
+    pub staked: i128,
+    pub locked: i128,
+    pub available: i128,
+}
+
 /// Storage keys for persistent data with proper indexing
 #[contracttype]
 #[derive(Clone)]
@@ -66,9 +204,11 @@ pub enum DataKey {
     /// Team data indexed by team name
     Teams(String),           
     /// Prediction data indexed by prediction ID
-    Predictions(String),     
-    /// User stakes indexed by user address
-    UserStakes(Address),     
+    Predictions(u64),
+    /// Prediction ID indexed by client label
+    PredictionLabels(String),
+    /// Member stake indexed by team name and member
+    MemberStakes(String, Address),
     /// Team membership indexed by user address for quick lookup
     UserTeams(Address),      
     /// KALE token contract address
@@ -77,80 +217,119 @@ pub enum DataKey {
     ReflectorOracle,        
     /// Contract admin address
     Admin,
-    /// Prediction counter for generating unique IDs
+    /// Proposed admin awaiting acceptance
+    PendingAdmin,
+    /// Storage layout version
+    SchemaVersion,
+    /// Team name at a registry position
+    TeamIndex(u32),
+    /// Registry position of a team name
+    TeamPosition(String),
+    /// Last prediction ID handed out
     PredictionCounter,
-    /// Team counter for generating unique IDs
+    /// Number of teams in the team registry
     TeamCounter,
+    /// Stake reserved by a member's open predictions indexed by team name and member
+    MemberLocked(String, Address),
+    /// Stake reserved by a team's open predictions
+    TeamLocked(String),
+    /// Reward owed indexed by prediction ID and beneficiary
+    Rewards(u64, Address),
+    /// Claimed flag indexed by prediction ID and beneficiary
+    RewardClaimed(u64, Address),
+    /// Prediction IDs with unclaimed rewards indexed by beneficiary
+    UserRewards(Address),
+    /// Pending invitation indexed by team name and invitee
+    Invites(String, Address),
+    /// Team names with pending invitations indexed by invitee
+    UserInvites(Address),
+    /// Invitees with pending invitations indexed by team name
+    TeamInvites(String),
+    /// Stake waiting out the unstake cooldown indexed by team name and member
+    Unstakes(String, Address),
+    /// Seconds unstaked KALE waits before it can be withdrawn
+    UnstakeCooldown,
+    /// Leading zero bits a work hash needs
+    MiningDifficulty,
 }
 
 #[contractimpl]
 impl CollaborativeDao {
-    /// Form a new team with the given name and members
+    /// Form a new team with the given name
+    ///
+    /// The first address is the creator and becomes the only member; every
+    /// other address is invited and joins once it accepts.
     pub fn form_team(
         env: Env,
         team_name: String,
         members: Vec<Address>,
     ) -> Result<(), Error> {
         // Authenticate the first member as team creator
-        members.get(0).unwrap().require_auth();
+        let creator = members.get(0).unwrap();
+        creator.require_auth();
 
         // Check if team already exists
         if env.storage().persistent().has(&DataKey::Teams(team_name.clone())) {
             return Err(Error::PredictionExists); // Reusing error for team exists
         }
 
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::UserTeams(creator.clone()))
+        {
+            return Err(Error::AlreadyInTeam);
+        }
+
         let team = Team {
             name: team_name.clone(),
-            members: members.clone(),
+            members: Vec::from_array(&env, [creator.clone()]),
             total_stake: 0,
+            roles: Map::from_array(&env, [(creator.clone(), MemberRole::Captain)]),
         };
 
         env.storage()
             .persistent()
             .set(&DataKey::Teams(team_name.clone()), &team);
 
-        // Track team membership for each member
-        for member in members.iter() {
-            env.storage()
-                .persistent()
-                .set(&DataKey::UserTeams(member.clone()), &team_name);
+        Self::register_team(&env, &team_name);
+
+        // Track team membership for the creator
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserTeams(creator.clone()), &team_name);
+
+        // Everyone else has to accept an invitation
+        for member in members.iter().skip(1) {
+            if member != creator {
+                Self::write_invite(&env, &team_name, &creator, &member);
+            }
         }
 
         Ok(())
     }
 
     /// Stake KALE tokens with adjustable percentage (0-100%)
+    ///
+    /// The amount is taken from the user's balance at execution time; returns
+    /// the KALE actually staked.
     pub fn stake_kale(
         env: Env,
         team_name: String,
         user: Address,
         stake_percentage: u32,
-    ) -> Result<(), Error> {
+    ) -> Result<i128, Error> {
         user.require_auth();
 
         if stake_percentage > 100 {
             return Err(Error::InvalidStakePercentage);
         }
 
-        // Validate team membership
-        Self::validate_team_member(&env, &team_name, &user)?;
-
-        // Get team
-        let mut team: Team = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Teams(team_name.clone()))
-            .unwrap(); // We know it exists from validation
-
-        // Get KALE token contract
-        let kale_token: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::KaleToken)
-            .unwrap();
+        // Every role may stake
+        let mut team = Self::require_role(&env, &team_name, &user, MemberRole::Staker)?;
 
-        let token_client = token::Client::new(&env, &kale_token);
-        let user_balance = token_client.balance(&user);
+        let kale_token = Self::kale_token(&env)?;
+        let user_balance = token::Client::new(&env, &kale_token).balance(&user);
 
         // Calculate stake amount based on percentage
         let stake_amount = (user_balance * stake_percentage as i128) / 100;
@@ -159,243 +338,342 @@ impl CollaborativeDao {
             return Err(Error::LowBalance);
         }
 
-        // Transfer KALE tokens to contract
-        token_client.transfer(&user, &env.current_contract_address(), &stake_amount);
+        Self::deposit_stake(&env, &mut team, &user, stake_amount)?;
 
-        // Update team total stake
-        team.total_stake += stake_amount;
-        env.storage()
-            .persistent()
-            .set(&DataKey::Teams(team_name), &team);
+        Ok(stake_amount)
+    }
 
-        // Update user stake tracking
-        let current_user_stake: i128 = env
-            .storage()
-            .persistent()
-            .get(&DataKey::UserStakes(user.clone()))
-            .unwrap_or(0);
-        
-        env.storage()
-            .persistent()
-            .set(&DataKey::UserStakes(user), &(current_user_stake + stake_amount));
+    /// Stake between `amount` and `max_amount` KALE tokens
+    ///
+    /// Stakes as much of the user's balance as fits under `max_amount`, and
+    /// fails with `LowBalance` if that is less than `amount`. Passing the same
+    /// value twice stakes exactly that amount. Returns the KALE actually staked.
+    pub fn stake_kale_amount(
+        env: Env,
+        team_name: String,
+        user: Address,
+        amount: i128,
+        max_amount: i128,
+    ) -> Result<i128, Error> {
+        user.require_auth();
 
-        Ok(())
+        if amount <= 0 || max_amount < amount {
+            return Err(Error::InvalidStakeAmount);
+        }
+
+        // Every role may stake
+        let mut team = Self::require_role(&env, &team_name, &user, MemberRole::Staker)?;
+
+        let kale_token = Self::kale_token(&env)?;
+        let user_balance = token::Client::new(&env, &kale_token).balance(&user);
+
+        let stake_amount = user_balance.min(max_amount);
+        if stake_amount < amount {
+            return Err(Error::LowBalance);
+        }
+
+        Self::deposit_stake(&env, &mut team, &user, stake_amount)?;
+
+        Ok(stake_amount)
     }
 
-    /// Submit a price prediction for an asset
+    /// Submit a price prediction for an asset and return its ID
+    ///
+    /// `stake_amount` is reserved from the predictor's staked KALE and stays
+    /// locked until the prediction is resolved. The outcome compares the price
+    /// now with the price at `resolve_after`, which must be at least
+    /// `SUBMISSION_CUTOFF` seconds away. An optional client `label` can be used
+    /// to look the ID up later and must be unique.
     pub fn make_prediction(
         env: Env,
-        prediction_id: String,
+        label: Option<String>,
         team_name: String,
         asset: String,
         prediction: bool,
         stake_amount: i128,
         stake_percentage: u32,
         predictor: Address,
-    ) -> Result<(), Error> {
+        resolve_after: u64,
+    ) -> Result<u64, Error> {
         predictor.require_auth();
 
-        // Check if prediction already exists
-        if env
-            .storage()
-            .persistent()
-            .has(&DataKey::Predictions(prediction_id.clone()))
-        {
-            return Err(Error::PredictionExists);
+        Self::validate_resolution_time(&env, resolve_after)?;
+
+        // Labels identify a single prediction
+        if let Some(label) = &label {
+            if env
+                .storage()
+                .persistent()
+                .has(&DataKey::PredictionLabels(label.clone()))
+            {
+                return Err(Error::PredictionExists);
+            }
         }
 
-        // Verify team exists
-        let _team: Team = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Teams(team_name.clone()))
-            .ok_or(Error::TeamNotFound)?;
+        if stake_amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
 
-        let prediction = Prediction {
+        // Verify team exists and the predictor may predict for it
+        Self::require_role(&env, &team_name, &predictor, MemberRole::Predictor)?;
+
+        // Escrow the stake out of the predictor's available balance
+        Self::escrow_stake(&env, &team_name, &predictor, stake_amount)?;
+
+        let prediction_id = Self::open_prediction(
+            &env,
             team_name,
             asset,
             prediction,
             stake_amount,
             stake_percentage,
             predictor,
-            timestamp: env.ledger().timestamp(),
-            resolved: false,
-            outcome: None,
-        };
-
-        env.storage()
-            .persistent()
-            .set(&DataKey::Predictions(prediction_id), &prediction);
+            resolve_after,
+        );
+        if let Some(label) = label {
+            env.storage()
+                .persistent()
+                .set(&DataKey::PredictionLabels(label), &prediction_id);
+        }
 
-        Ok(())
+        Ok(prediction_id)
     }
 
     /// Resolve a prediction using Reflector oracle data
+    ///
+    /// `nonce` must turn the prediction into a work hash with at least
+    /// `get_difficulty(asset)` leading zero bits (see `get_work_hash`). The
+    /// prices must pass the asset's oracle guard (see `get_oracle_guard`).
+    /// Returns whether the prediction was correct; an unchanged price voids
+    /// the prediction with no outcome and returns the stake.
     pub fn resolve_prediction(
         env: Env,
-        prediction_id: String,
+        prediction_id: u64,
+        nonce: u64,
     ) -> Result<bool, Error> {
         let mut prediction: Prediction = env
             .storage()
             .persistent()
-            .get(&DataKey::Predictions(prediction_id.clone()))
+            .get(&DataKey::Predictions(prediction_id))
             .ok_or(Error::PredictionNotFound)?;
 
         if prediction.resolved {
             return Err(Error::AlreadyResolved);
         }
 
-        // Get Reflector oracle address from storage
-        let reflector_address: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::ReflectorOracle)
-            .ok_or(Error::OracleDataUnavailable)?;
-
-        let reflector_client = ReflectorClient::new(&env, &reflector_address);
-
-        // For EUR/USD predictions, query EUR from Reflector using ReflectorAsset::Other
-        let asset_symbol = if prediction.asset == String::from_str(&env, "EUR/USD") {
-            String::from_str(&env, "EUR")
-        } else {
-            prediction.asset.clone()
-        };
-
-        // Fetch current price from Reflector oracle
-        let current_data = reflector_client
-            .get_last_price(&env, asset_symbol.clone())
-            .ok_or(Error::OracleDataUnavailable)?;
+        let now = env.ledger().timestamp();
+        if now < prediction.resolve_after {
+            return Err(Error::ResolutionTooEarly);
+        }
+        if now > prediction.expires_at {
+            return Err(Error::PredictionExpired);
+        }
 
-        // Get historical price at prediction time for proper comparison
-        // In production, this would use historical price data from Reflector
-        let historical_data = reflector_client
-            .get_price(&env, asset_symbol, prediction.timestamp)
-            .ok_or(Error::OracleDataUnavailable)?;
+        // Fetch the price at prediction time and at the resolution horizon,
+        // and run the staleness and circuit breaker checks before any work is
+        // judged against them
+        let (historical_data, horizon_data) = Self::prediction_prices(&env, &prediction)?;
+        Self::check_prices(&env, prediction_id, &prediction, &historical_data, &horizon_data)?;
 
-        // KALE Mining Hash Verification using Env.crypto().hash
-        // Generate KALE mining hash combining team, prediction, and oracle data
-        // For MVP, create a simple hash input using available data
-        let hash_bytes = soroban_sdk::bytes!(&env, 0x6b616c65); // "kale" in hex
-        
-        // Generate KALE mining hash using Soroban's crypto.hash
-        let kale_hash = env.crypto().keccak256(&hash_bytes);
-        
-        // Extract hash components for verification
-        let hash_bytes = kale_hash.to_array();
-        let hash_sum: u32 = hash_bytes[0] as u32 + hash_bytes[1] as u32 + hash_bytes[2] as u32 + hash_bytes[3] as u32;
-        
-        // KALE mining verification: hash must meet difficulty criteria
-        let difficulty_target = if prediction.stake_percentage > 0 { prediction.stake_percentage } else { 1 };
-        let hash_verification_passed = (hash_sum % difficulty_target) == 0;
-        
-        // Oracle data consistency check
-        let oracle_data_valid = current_data.price != historical_data.price && current_data.price > 0 && historical_data.price > 0;
-        
-        if !hash_verification_passed {
+        // KALE mining: the nonce has to turn the team, prediction and oracle
+        // prices into a hash with enough leading zero bits
+        let work_hash = Self::work_hash(
+            &env,
+            prediction_id,
+            &prediction,
+            &prediction.predictor,
+            historical_data.price,
+            horizon_data.price,
+            nonce,
+        );
+        let zero_bits = Self::leading_zero_bits(&work_hash);
+        let difficulty = Self::get_difficulty(env.clone(), prediction.asset.clone());
+
+        if zero_bits < difficulty {
             // Return specific KALE mining hash verification failure
             env.events().publish((
                 "kale_hash_failed", 
-                prediction_id.clone(),
-                hash_sum,
-                difficulty_target
+                prediction_id,
+                zero_bits,
+                difficulty
             ), None::<String>);
             return Err(Error::HashVerificationFailed);
         }
 
-        if !oracle_data_valid {
-            env.events().publish((
-                "oracle_data_invalid", 
-                prediction_id.clone(),
-                current_data.price,
-                historical_data.price
-            ), None::<String>);
-            return Err(Error::OracleDataUnavailable);
-        }
+        Self::record_submission(&env, &prediction.asset);
 
         // Log successful KALE mining hash verification
         env.events().publish((
             "kale_hash_verified", 
-            prediction_id.clone(),
-            hash_sum,
-            difficulty_target
+            prediction_id,
+            zero_bits,
+            difficulty
         ), None::<String>);
 
-        // Determine if prediction was correct based on price movement
-        let price_increased = current_data.price > historical_data.price;
-        let prediction_correct = prediction.prediction == price_increased;
+        let outcome = Self::settle_prediction(&env, prediction_id, &mut prediction, &historical_data, &horizon_data, true);
 
-        // Mark prediction as resolved
-        prediction.resolved = true;
-        prediction.outcome = Some(prediction_correct);
+        Ok(outcome == Some(true))
+    }
 
-        // Store updated prediction
+    /// Close an expired prediction that was never resolved
+    ///
+    /// A prediction the oracle has prices for is settled against them
+    /// without mining: a losing stake is slashed and a winning or unchanged
+    /// one is released without a bonus. Only when the oracle has no usable
+    /// price is the stake released and the prediction closed without an
+    /// outcome. Prices tripping the circuit breaker fail with
+    /// `PriceDeviation` until the admin reviews the asset's guard.
+    pub fn refund_prediction(env: Env, prediction_id: u64) -> Result<(), Error> {
+        let mut prediction: Prediction = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Predictions(prediction_id))
+            .ok_or(Error::PredictionNotFound)?;
+
+        if prediction.resolved {
+            return Err(Error::AlreadyResolved);
+        }
+        if env.ledger().timestamp() <= prediction.expires_at {
+            return Err(Error::NotExpired);
+        }
+
+        let prices = Self::prediction_prices(&env, &prediction).and_then(|(start, end)| {
+            Self::check_prices(&env, prediction_id, &prediction, &start, &end)?;
+            Ok((start, end))
+        });
+        match prices {
+            Ok((start, end)) => {
+                Self::settle_prediction(&env, prediction_id, &mut prediction, &start, &end, false);
+                return Ok(());
+            }
+            Err(Error::OracleDataUnavailable) | Err(Error::StalePrice) => {}
+            Err(error) => return Err(error),
+        }
+
+        Self::release_stake(&env, &prediction.team_name, &prediction.predictor, prediction.stake_amount);
+
+        // Closed without an outcome
+        prediction.resolved = true;
+        prediction.outcome = None;
         env.storage()
             .persistent()
-            .set(&DataKey::Predictions(prediction_id.clone()), &prediction);
+            .set(&DataKey::Predictions(prediction_id), &prediction);
 
-        // Log the resolution for debugging
         env.events().publish((
-            "prediction_resolved", 
+            "prediction_refunded",
             prediction_id,
-            prediction_correct,
-            current_data.price,
-            historical_data.price
+            prediction.stake_amount
         ), None::<String>);
 
-        Ok(prediction_correct)
+        Ok(())
     }
-
-    /// Distribute KALE rewards based on prediction accuracy and contributions
-    pub fn distribute_rewards(
+    /// Claim the KALE reward owed to `claimant` for a resolved prediction
+    ///
+    /// Each beneficiary can claim a prediction's reward exactly once.
+    pub fn claim_reward(
         env: Env,
-        prediction_id: String,
-    ) -> Result<(), Error> {
-        let prediction: Prediction = env
+        prediction_id: u64,
+        claimant: Address,
+    ) -> Result<i128, Error> {
+        claimant.require_auth();
+
+        if !env
             .storage()
             .persistent()
-            .get(&DataKey::Predictions(prediction_id))
-            .ok_or(Error::PredictionNotFound)?;
-
-        if !prediction.resolved {
+            .has(&DataKey::Predictions(prediction_id))
+        {
             return Err(Error::PredictionNotFound);
         }
 
-        let prediction_correct = prediction.outcome.unwrap_or(false);
-
-        if prediction_correct {
-            // Get KALE token contract
-            let kale_token: Address = env
-                .storage()
-                .persistent()
-                .get(&DataKey::KaleToken)
-                .unwrap();
+        if Self::is_reward_claimed(env.clone(), prediction_id, claimant.clone()) {
+            return Err(Error::RewardAlreadyClaimed);
+        }
 
-            let token_client = token::Client::new(&env, &kale_token);
+        let reward_amount: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Rewards(prediction_id, claimant.clone()))
+            .ok_or(Error::NoReward)?;
 
-            // Calculate reward (simple logic: return staked amount + 10% bonus)
-            let reward_amount = prediction.stake_amount + (prediction.stake_amount / 10);
+        // Mark as claimed before transferring
+        env.storage()
+            .persistent()
+            .set(&DataKey::RewardClaimed(prediction_id, claimant.clone()), &true);
 
-            // Transfer reward to predictor
-            token_client.transfer(
-                &env.current_contract_address(),
-                &prediction.predictor,
-                &reward_amount,
-            );
+        let mut pending: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserRewards(claimant.clone()))
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = pending.first_index_of(prediction_id) {
+            pending.remove(index);
         }
-        // If prediction was wrong, staked tokens remain in contract (penalty)
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserRewards(claimant.clone()), &pending);
 
-        Ok(())
+        Self::pay_out(&env, &claimant, reward_amount, &TreasuryKey::PendingRewards)?;
+
+        env.events().publish((
+            "reward_claimed",
+            prediction_id,
+            claimant,
+            reward_amount
+        ), None::<String>);
+
+        Ok(reward_amount)
+    }
+
+    /// Get the total unclaimed KALE rewards owed to a user
+    pub fn get_claimable(env: Env, user: Address) -> i128 {
+        let pending: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserRewards(user.clone()))
+            .unwrap_or(Vec::new(&env));
+
+        pending
+            .iter()
+            .map(|prediction_id| {
+                env.storage()
+                    .persistent()
+                    .get::<_, i128>(&DataKey::Rewards(prediction_id, user.clone()))
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    /// Check whether a beneficiary has claimed their reward for a prediction
+    pub fn is_reward_claimed(env: Env, prediction_id: u64, beneficiary: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RewardClaimed(prediction_id, beneficiary))
+            .unwrap_or(false)
     }
 
     /// Initialize the contract with KALE token and Reflector oracle addresses
+    ///
+    /// Can only be called once; the admin is stored for later configuration changes.
     pub fn initialize(
         env: Env,
         admin: Address,
         kale_token: Address,
         reflector_oracle: Address,
     ) -> Result<(), Error> {
+        if env.storage().persistent().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+
         admin.require_auth();
 
+        env.storage()
+            .persistent()
+            .set(&DataKey::Admin, &admin);
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+
         env.storage()
             .persistent()
             .set(&DataKey::KaleToken, &kale_token);
@@ -407,6 +685,72 @@ impl CollaborativeDao {
         Ok(())
     }
 
+    /// Replace the KALE token contract address (admin only)
+    pub fn set_kale_token(env: Env, kale_token: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::KaleToken, &kale_token);
+
+        env.events().publish(("kale_token_updated", kale_token), None::<String>);
+
+        Ok(())
+    }
+
+    /// Replace the Reflector oracle contract address (admin only)
+    pub fn set_reflector_oracle(env: Env, reflector_oracle: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::ReflectorOracle, &reflector_oracle);
+
+        env.events().publish(("reflector_oracle_updated", reflector_oracle), None::<String>);
+
+        Ok(())
+    }
+
+    /// Propose a new admin; takes effect once the new admin calls `accept_admin`
+    pub fn transfer_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingAdmin, &new_admin);
+
+        Ok(())
+    }
+
+    /// Accept a pending admin transfer
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        let new_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingAdmin)
+            .ok_or(Error::Unauthorized)?;
+
+        new_admin.require_auth();
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Admin, &new_admin);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingAdmin);
+
+        env.events().publish(("admin_transferred", new_admin), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the admin proposed by `transfer_admin`, if any
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PendingAdmin)
+    }
+
     /// Get team information
     pub fn get_team(env: Env, team_name: String) -> Option<Team> {
         env.storage()
@@ -415,20 +759,80 @@ impl CollaborativeDao {
     }
 
     /// Get prediction information
-    pub fn get_prediction(env: Env, prediction_id: String) -> Option<Prediction> {
+    pub fn get_prediction(env: Env, prediction_id: u64) -> Option<Prediction> {
         env.storage()
             .persistent()
             .get(&DataKey::Predictions(prediction_id))
     }
 
-    /// Get user's total staked amount
+    /// Get the ID of the prediction submitted with a client label
+    pub fn get_prediction_id(env: Env, label: String) -> Option<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PredictionLabels(label))
+    }
+
+    /// Get user's staked amount in their current team
     pub fn get_user_stake(env: Env, user: Address) -> i128 {
+        Self::get_user_balance(env, user).staked
+    }
+
+    /// Get user's staked, locked and available KALE in their current team
+    pub fn get_user_balance(env: Env, user: Address) -> StakeBalance {
+        match Self::get_user_team(env.clone(), user.clone()) {
+            Some(team_name) => Self::get_member_balance(env, team_name, user),
+            None => StakeBalance {
+                staked: 0,
+                locked: 0,
+                available: 0,
+            },
+        }
+    }
+
+    /// Get a member's staked amount in a team
+    pub fn get_member_stake(env: Env, team_name: String, member: Address) -> i128 {
         env.storage()
             .persistent()
-            .get(&DataKey::UserStakes(user))
+            .get(&DataKey::MemberStakes(team_name, member))
             .unwrap_or(0)
     }
 
+    /// Get a member's staked, locked and available KALE in a team
+    pub fn get_member_balance(env: Env, team_name: String, member: Address) -> StakeBalance {
+        let staked = Self::get_member_stake(env.clone(), team_name.clone(), member.clone());
+        let locked: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::MemberLocked(team_name, member))
+            .unwrap_or(0);
+
+        StakeBalance {
+            staked,
+            locked,
+            available: staked - locked,
+        }
+    }
+
+    /// Get team's staked, locked and available KALE
+    pub fn get_team_balance(env: Env, team_name: String) -> Result<StakeBalance, Error> {
+        let team: Team = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Teams(team_name.clone()))
+            .ok_or(Error::TeamNotFound)?;
+        let locked: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TeamLocked(team_name))
+            .unwrap_or(0);
+
+        Ok(StakeBalance {
+            staked: team.total_stake,
+            locked,
+            available: team.total_stake - locked,
+        })
+    }
+
     /// Get user's team membership
     pub fn get_user_team(env: Env, user: Address) -> Option<String> {
         env.storage()
@@ -436,49 +840,367 @@ impl CollaborativeDao {
             .get(&DataKey::UserTeams(user))
     }
 
-    /// List all teams (for UI purposes)
-    pub fn list_teams(env: Env) -> Vec<String> {
-        // This is a simplified implementation
-        // In production, you'd maintain a separate list of team names
-        soroban_sdk::vec![&env]
+    /// List registered teams with summary stats, `limit` (at most `MAX_TEAM_PAGE`) entries from `start`
+    pub fn list_teams(env: Env, start: u32, limit: u32) -> Vec<TeamSummary> {
+        let count = Self::team_count(env.clone());
+        let end = count.min(start.saturating_add(limit.min(MAX_TEAM_PAGE)));
+
+        let mut teams = Vec::new(&env);
+        for position in start..end {
+            let name: String = env
+                .storage()
+                .persistent()
+                .get(&DataKey::TeamIndex(position))
+                .unwrap();
+            let team: Team = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Teams(name.clone()))
+                .unwrap();
+
+            teams.push_back(TeamSummary {
+                name,
+                member_count: team.members.len(),
+                total_stake: team.total_stake,
+            });
+        }
+        teams
+    }
+
+    /// Get the number of registered teams
+    pub fn team_count(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TeamCounter)
+            .unwrap_or(0)
     }
 
-    /// Get contract configuration
-    pub fn get_config(env: Env) -> (Option<Address>, Option<Address>) {
+    /// Get contract configuration as (KALE token, Reflector oracle, admin)
+    pub fn get_config(env: Env) -> (Option<Address>, Option<Address>, Option<Address>) {
         let kale_token = env.storage().persistent().get(&DataKey::KaleToken);
         let reflector_oracle = env.storage().persistent().get(&DataKey::ReflectorOracle);
-        (kale_token, reflector_oracle)
+        let admin = env.storage().persistent().get(&DataKey::Admin);
+        (kale_token, reflector_oracle, admin)
     }
 
-    /// Generate unique prediction ID
-    #[allow(dead_code)]
-    fn generate_prediction_id(env: &Env) -> String {
+    /// Generate unique prediction ID, starting at 1
+    fn generate_prediction_id(env: &Env) -> u64 {
         let counter: u64 = env
             .storage()
             .persistent()
             .get(&DataKey::PredictionCounter)
             .unwrap_or(0);
-        
+        let prediction_id = counter + 1;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::PredictionCounter, &prediction_id);
+
+        prediction_id
+    }
+
+    /// Check that a resolution time leaves the submission cutoff and stays within the horizon
+    fn validate_resolution_time(env: &Env, resolve_after: u64) -> Result<(), Error> {
+        let now = env.ledger().timestamp();
+        if resolve_after < now + SUBMISSION_CUTOFF {
+            return Err(Error::SubmissionClosed);
+        }
+        if resolve_after > now + MAX_PREDICTION_HORIZON {
+            return Err(Error::InvalidHorizon);
+        }
+        Ok(())
+    }
+
+    /// Store a new prediction made now, with its stake already escrowed
+    fn open_prediction(
+        env: &Env,
+        team_name: String,
+        asset: String,
+        prediction: bool,
+        stake_amount: i128,
+        stake_percentage: u32,
+        predictor: Address,
+        resolve_after: u64,
+    ) -> u64 {
+        let prediction_id = Self::generate_prediction_id(env);
+
+        let prediction = Prediction {
+            team_name,
+            asset,
+            prediction,
+            stake_amount,
+            stake_percentage,
+            predictor,
+            timestamp: env.ledger().timestamp(),
+            resolve_after,
+            expires_at: resolve_after + RESOLUTION_WINDOW,
+            resolved: false,
+            outcome: None,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Predictions(prediction_id), &prediction);
+
+        prediction_id
+    }
+
+    /// Record a claimable reward for a prediction beneficiary
+    fn record_reward(env: &Env, prediction_id: u64, beneficiary: &Address, amount: i128) {
+        let reward_key = DataKey::Rewards(prediction_id, beneficiary.clone());
+        let current: i128 = env.storage().persistent().get(&reward_key).unwrap_or(0);
+        env.storage().persistent().set(&reward_key, &(current + amount));
+        Self::credit(env, &TreasuryKey::PendingRewards, amount);
+
+        let mut pending: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::UserRewards(beneficiary.clone()))
+            .unwrap_or(Vec::new(env));
+        if !pending.contains(prediction_id) {
+            pending.push_back(prediction_id);
+            env.storage()
+                .persistent()
+                .set(&DataKey::UserRewards(beneficiary.clone()), &pending);
+        }
+    }
+
+    /// Transfer KALE from a member into the contract and credit it to their
+    /// team stake
+    fn deposit_stake(env: &Env, team: &mut Team, user: &Address, amount: i128) -> Result<(), Error> {
+        let kale_token = Self::kale_token(env)?;
+        token::Client::new(env, &kale_token).transfer(user, &env.current_contract_address(), &amount);
+        Self::credit(env, &TreasuryKey::Stakes, amount);
+
+        team.total_stake += amount;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team.name.clone()), &*team);
+
+        let member_stake = Self::get_member_stake(env.clone(), team.name.clone(), user.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::MemberStakes(team.name.clone(), user.clone()), &(member_stake + amount));
+
+        Ok(())
+    }
+
+    /// Fetch the oracle prices at a prediction's timestamp and resolution time
+    fn prediction_prices(env: &Env, prediction: &Prediction) -> Result<(PriceData, PriceData), Error> {
+        let reflector_address: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ReflectorOracle)
+            .ok_or(Error::OracleDataUnavailable)?;
+        let reflector_client = ReflectorClient::new(env, &reflector_address);
+
+        // Pairs such as "EUR/USD" are resolved against the base ticker by the client
+        let start = reflector_client.get_price(env, prediction.asset.clone(), prediction.timestamp)?;
+        let end = reflector_client.get_price(env, prediction.asset.clone(), prediction.resolve_after)?;
+
+        Ok((start, end))
+    }
+
+    /// Close a prediction on its start and horizon prices
+    ///
+    /// An unchanged price voids the prediction. Otherwise a losing stake is
+    /// slashed, and a winning one is paid out with the bonus if `rewarded`
+    /// or simply released if not. Returns the outcome.
+    fn settle_prediction(
+        env: &Env,
+        prediction_id: u64,
+        prediction: &mut Prediction,
+        start: &PriceData,
+        end: &PriceData,
+        rewarded: bool,
+    ) -> Option<bool> {
+        // Either way the escrowed stake is released
+        Self::release_stake(env, &prediction.team_name, &prediction.predictor, prediction.stake_amount);
+        prediction.resolved = true;
+
+        // An unchanged price has no direction to be right or wrong about; the
+        // prediction is voided and the stake returned
+        if end.price == start.price {
+            prediction.outcome = None;
+            env.storage()
+                .persistent()
+                .set(&DataKey::Predictions(prediction_id), &*prediction);
+
+            env.events().publish((
+                "prediction_voided",
+                prediction_id,
+                end.price
+            ), None::<String>);
+
+            return None;
+        }
+
+        // Determine if prediction was correct based on price movement
+        let price_increased = end.price > start.price;
+        let prediction_correct = prediction.prediction == price_increased;
+        prediction.outcome = Some(prediction_correct);
+
+        // A rewarded winning stake leaves the staked balance and becomes
+        // claimable together with the bonus, and a losing stake is slashed
+        if prediction_correct && rewarded {
+            Self::deduct_stake(env, &prediction.team_name, &prediction.predictor, prediction.stake_amount);
+            Self::credit(env, &TreasuryKey::Stakes, -prediction.stake_amount);
+
+            // Return the staked amount plus a 10% bonus paid by the reward
+            // pool, boosted by the team's mining work, and fund the farmers
+            // who planted on the block
+            Self::reward_winners(env, prediction_id, prediction);
+            Self::fund_harvest(env, prediction_id);
+        } else if !prediction_correct {
+            Self::slash_stake(
+                env,
+                prediction_id,
+                &prediction.team_name,
+                &prediction.predictor,
+                prediction.stake_amount,
+            );
+        }
+
+        // Store updated prediction
+        env.storage()
+            .persistent()
+            .set(&DataKey::Predictions(prediction_id), &*prediction);
+
+        // Log the resolution for debugging
+        env.events().publish((
+            "prediction_resolved",
+            prediction_id,
+            prediction_correct,
+            end.price,
+            start.price
+        ), None::<String>);
+
+        Some(prediction_correct)
+    }
+
+    /// Reserve stake out of a member's available balance in the team
+    fn escrow_stake(env: &Env, team_name: &String, user: &Address, amount: i128) -> Result<(), Error> {
+        let balance = Self::get_member_balance(env.clone(), team_name.clone(), user.clone());
+        if amount > balance.available {
+            return Err(Error::LowBalance);
+        }
+        Self::lock_stake(env, team_name, user, amount);
+        Ok(())
+    }
+
+    /// Reserve stake for an open prediction
+    fn lock_stake(env: &Env, team_name: &String, user: &Address, amount: i128) {
+        Self::adjust_locked(env, team_name, user, amount);
+    }
+
+    /// Return reserved stake to the available balance
+    fn release_stake(env: &Env, team_name: &String, user: &Address, amount: i128) {
+        Self::adjust_locked(env, team_name, user, -amount);
+    }
+
+    fn adjust_locked(env: &Env, team_name: &String, user: &Address, delta: i128) {
+        let user_key = DataKey::MemberLocked(team_name.clone(), user.clone());
+        let user_locked: i128 = env.storage().persistent().get(&user_key).unwrap_or(0);
+        env.storage().persistent().set(&user_key, &(user_locked + delta));
+
+        let team_key = DataKey::TeamLocked(team_name.clone());
+        let team_locked: i128 = env.storage().persistent().get(&team_key).unwrap_or(0);
+        env.storage().persistent().set(&team_key, &(team_locked + delta));
+    }
+
+    /// Remove stake from a member's and team's staked balance
+    fn deduct_stake(env: &Env, team_name: &String, user: &Address, amount: i128) {
+        let user_key = DataKey::MemberStakes(team_name.clone(), user.clone());
+        let user_stake: i128 = env.storage().persistent().get(&user_key).unwrap_or(0);
+        env.storage().persistent().set(&user_key, &(user_stake - amount));
+
+        let team_key = DataKey::Teams(team_name.clone());
+        if let Some(mut team) = env.storage().persistent().get::<_, Team>(&team_key) {
+            team.total_stake -= amount;
+            env.storage().persistent().set(&team_key, &team);
+        }
+    }
+
+    /// Append a team to the registry
+    fn register_team(env: &Env, team_name: &String) {
+        let count = Self::team_count(env.clone());
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::TeamIndex(count), team_name);
+        env.storage()
+            .persistent()
+            .set(&DataKey::TeamPosition(team_name.clone()), &count);
+        env.storage()
+            .persistent()
+            .set(&DataKey::TeamCounter, &(count + 1));
+    }
+
+    /// Remove a team from the registry by moving the last entry into its slot
+    fn unregister_team(env: &Env, team_name: &String) {
+        let position_key = DataKey::TeamPosition(team_name.clone());
+        let Some(position) = env.storage().persistent().get::<_, u32>(&position_key) else {
+            return;
+        };
+
+        let last = Self::team_count(env.clone()) - 1;
+        if position != last {
+            let moved: String = env
+                .storage()
+                .persistent()
+                .get(&DataKey::TeamIndex(last))
+                .unwrap();
+            env.storage()
+                .persistent()
+                .set(&DataKey::TeamIndex(position), &moved);
+            env.storage()
+                .persistent()
+                .set(&DataKey::TeamPosition(moved), &position);
+        }
+
+        env.storage().persistent().remove(&DataKey::TeamIndex(last));
+        env.storage().persistent().remove(&position_key);
+        env.storage()
+            .persistent()
+            .set(&DataKey::TeamCounter, &last);
+    }
+
+    /// Load the stored admin and require its authorization
+    fn require_admin(env: &Env) -> Result<Address, Error> {
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Get the configured KALE token contract
+    fn kale_token(env: &Env) -> Result<Address, Error> {
         env.storage()
             .persistent()
-            .set(&DataKey::PredictionCounter, &(counter + 1));
-            
-        // Create a simple string representation
-        String::from_str(env, "prediction")
+            .get(&DataKey::KaleToken)
+            .ok_or(Error::NotInitialized)
     }
 
-    /// Validate team membership
-    fn validate_team_member(env: &Env, team_name: &String, user: &Address) -> Result<(), Error> {
+    /// Validate that a user holds at least the `required` role in a team
+    fn require_role(
+        env: &Env,
+        team_name: &String,
+        user: &Address,
+        required: MemberRole,
+    ) -> Result<Team, Error> {
         let team: Team = env
             .storage()
             .persistent()
             .get(&DataKey::Teams(team_name.clone()))
             .ok_or(Error::TeamNotFound)?;
 
-        if !team.members.contains(user) {
-            return Err(Error::Unauthorized);
+        match team.roles.get(user.clone()) {
+            Some(role) if role >= required => Ok(team),
+            _ => Err(Error::Unauthorized),
         }
-        Ok(())
     }
 }
 
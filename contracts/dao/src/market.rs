@@ -0,0 +1,403 @@
+use soroban_sdk::{contractimpl, contracttype, token, Address, Env, String};
+
+use crate::reflector::ReflectorClient;
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, DataKey, Error, MemberRole,
+    TreasuryKey, BPS_DENOMINATOR, RESOLUTION_WINDOW,
+};
+
+/// Upper bound on the protocol fee taken from a round's losing pool (10%)
+pub const MAX_MARKET_FEE_BPS: u32 = 1_000;
+
+/// Parimutuel market on an asset's price direction over a fixed horizon
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Market {
+    pub asset: String,
+    pub horizon: u64, // seconds of entry window and of price measurement
+    pub fee_bps: u32, // protocol fee on the losing pool
+    pub creator: Address,
+    pub round_count: u32,
+}
+
+/// Lifecycle of a market round
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RoundStatus {
+    /// Accepting entries until `lock_time`, settleable after `close_time`
+    Open,
+    /// Winners split the pools
+    Settled,
+    /// One-sided pool, unchanged price or missed settlement deadline; every
+    /// entry is refunded
+    Void,
+}
+
+/// A single betting round of a market
+///
+/// Entries are accepted until `lock_time`. The outcome compares the oracle
+/// price at `lock_time` with the price at `close_time`. A round still open
+/// `RESOLUTION_WINDOW` after `close_time` settles as void.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Round {
+    pub lock_time: u64,
+    pub close_time: u64,
+    pub rise_pool: i128,
+    pub fall_pool: i128,
+    pub rise_entries: u32,
+    pub fall_entries: u32,
+    pub status: RoundStatus,
+    pub outcome: Option<bool>, // true = price rose
+    pub fee: i128,
+    pub payout_pool: i128,     // winning pool plus losing pool minus fee
+    pub claimed: i128,
+    pub claims_remaining: u32,
+}
+
+/// A member's stake in a round, made on behalf of their team
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundEntry {
+    pub team_name: String,
+    pub rise: bool,
+    pub amount: i128,
+    pub claimed: bool,
+}
+
+/// Storage keys for markets, kept alongside `DataKey`
+#[contracttype]
+#[derive(Clone)]
+pub enum MarketKey {
+    /// Counter for generating market IDs
+    MarketCounter,
+    /// Market data indexed by market ID
+    Markets(u32),
+    /// Round data indexed by market ID and round ID
+    Rounds(u32, u32),
+    /// Member entry indexed by market ID, round ID and member
+    Entries(u32, u32, Address),
+    /// Side taken by a team in a round
+    TeamSides(u32, u32, String),
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Create a market on `asset` with rounds of `horizon` seconds
+    pub fn create_market(
+        env: Env,
+        creator: Address,
+        asset: String,
+        horizon: u64,
+        fee_bps: u32,
+    ) -> Result<u32, Error> {
+        creator.require_auth();
+
+        if horizon == 0 {
+            return Err(Error::InvalidHorizon);
+        }
+        if fee_bps > MAX_MARKET_FEE_BPS {
+            return Err(Error::InvalidFee);
+        }
+
+        let market_id: u32 = env
+            .storage()
+            .persistent()
+            .get(&MarketKey::MarketCounter)
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&MarketKey::MarketCounter, &(market_id + 1));
+
+        let market = Market {
+            asset,
+            horizon,
+            fee_bps,
+            creator,
+            round_count: 0,
+        };
+        env.storage()
+            .persistent()
+            .set(&MarketKey::Markets(market_id), &market);
+
+        Ok(market_id)
+    }
+
+    /// Open the next round of a market once the previous round has locked
+    pub fn open_round(env: Env, market_id: u32) -> Result<u32, Error> {
+        let mut market = Self::get_market(env.clone(), market_id).ok_or(Error::MarketNotFound)?;
+        let now = env.ledger().timestamp();
+
+        if market.round_count > 0 {
+            let previous = Self::load_round(&env, market_id, market.round_count - 1)?;
+            if now < previous.lock_time {
+                return Err(Error::RoundInProgress);
+            }
+        }
+
+        let round_id = market.round_count;
+        let lock_time = now + market.horizon;
+        let round = Round {
+            lock_time,
+            close_time: lock_time + market.horizon,
+            rise_pool: 0,
+            fall_pool: 0,
+            rise_entries: 0,
+            fall_entries: 0,
+            status: RoundStatus::Open,
+            outcome: None,
+            fee: 0,
+            payout_pool: 0,
+            claimed: 0,
+            claims_remaining: 0,
+        };
+        env.storage()
+            .persistent()
+            .set(&MarketKey::Rounds(market_id, round_id), &round);
+
+        market.round_count += 1;
+        env.storage()
+            .persistent()
+            .set(&MarketKey::Markets(market_id), &market);
+
+        Ok(round_id)
+    }
+
+    /// Stake KALE on a round's direction on behalf of the member's team
+    ///
+    /// A team can only back one side of a round; repeated entries by the same
+    /// member add to their stake.
+    pub fn enter_round(
+        env: Env,
+        market_id: u32,
+        round_id: u32,
+        team_name: String,
+        member: Address,
+        rise: bool,
+        amount: i128,
+    ) -> Result<(), Error> {
+        member.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
+
+        Self::require_role(&env, &team_name, &member, MemberRole::Predictor)?;
+
+        let mut round = Self::load_round(&env, market_id, round_id)?;
+        if env.ledger().timestamp() >= round.lock_time {
+            return Err(Error::RoundClosed);
+        }
+
+        let side_key = MarketKey::TeamSides(market_id, round_id, team_name.clone());
+        if let Some(team_side) = env.storage().persistent().get::<_, bool>(&side_key) {
+            if team_side != rise {
+                return Err(Error::ConflictingSide);
+            }
+        }
+
+        let entry_key = MarketKey::Entries(market_id, round_id, member.clone());
+        let mut entry = match env.storage().persistent().get::<_, RoundEntry>(&entry_key) {
+            Some(entry) => {
+                if entry.rise != rise || entry.team_name != team_name {
+                    return Err(Error::ConflictingSide);
+                }
+                entry
+            }
+            None => {
+                if rise {
+                    round.rise_entries += 1;
+                } else {
+                    round.fall_entries += 1;
+                }
+                RoundEntry {
+                    team_name: team_name.clone(),
+                    rise,
+                    amount: 0,
+                    claimed: false,
+                }
+            }
+        };
+
+        // Transfer KALE tokens to contract
+        let kale_token = Self::kale_token(&env)?;
+        token::Client::new(&env, &kale_token).transfer(
+            &member,
+            &env.current_contract_address(),
+            &amount,
+        );
+        Self::credit(&env, &TreasuryKey::MarketEntries, amount);
+
+        entry.amount += amount;
+        if rise {
+            round.rise_pool += amount;
+        } else {
+            round.fall_pool += amount;
+        }
+
+        env.storage().persistent().set(&side_key, &rise);
+        env.storage().persistent().set(&entry_key, &entry);
+        env.storage()
+            .persistent()
+            .set(&MarketKey::Rounds(market_id, round_id), &round);
+
+        Ok(())
+    }
+
+    /// Settle a round from the oracle prices at its lock and close times
+    ///
+    /// One-sided rounds are void without consulting the oracle. Once
+    /// `RESOLUTION_WINDOW` has passed since `close_time`, anyone can settle a
+    /// round as void so its entries can be refunded.
+    pub fn settle_round(env: Env, market_id: u32, round_id: u32) -> Result<RoundStatus, Error> {
+        let market = Self::get_market(env.clone(), market_id).ok_or(Error::MarketNotFound)?;
+        let mut round = Self::load_round(&env, market_id, round_id)?;
+
+        if round.status != RoundStatus::Open {
+            return Err(Error::AlreadyResolved);
+        }
+        let now = env.ledger().timestamp();
+        if now < round.close_time {
+            return Err(Error::RoundNotClosed);
+        }
+
+        let outcome = if round.rise_pool == 0
+            || round.fall_pool == 0
+            || now > round.close_time + RESOLUTION_WINDOW
+        {
+            None
+        } else {
+            let reflector_address: Address = env
+                .storage()
+                .persistent()
+                .get(&DataKey::ReflectorOracle)
+                .ok_or(Error::OracleDataUnavailable)?;
+            let reflector_client = ReflectorClient::new(&env, &reflector_address);
+
+            let start = reflector_client.get_price(&env, market.asset.clone(), round.lock_time)?;
+            let end = reflector_client.get_price(&env, market.asset, round.close_time)?;
+            (start.price != end.price).then_some(end.price > start.price)
+        };
+
+        if let Some(rose) = outcome {
+            let (winning_pool, losing_pool, winners) = if rose {
+                (round.rise_pool, round.fall_pool, round.rise_entries)
+            } else {
+                (round.fall_pool, round.rise_pool, round.fall_entries)
+            };
+
+            round.fee = losing_pool * market.fee_bps as i128 / BPS_DENOMINATOR;
+            round.payout_pool = winning_pool + losing_pool - round.fee;
+            round.claims_remaining = winners;
+            round.outcome = Some(rose);
+            round.status = RoundStatus::Settled;
+
+            Self::credit(&env, &TreasuryKey::Treasury, round.fee);
+            Self::credit(&env, &TreasuryKey::MarketEntries, -round.fee);
+        } else {
+            round.status = RoundStatus::Void;
+        }
+
+        env.storage()
+            .persistent()
+            .set(&MarketKey::Rounds(market_id, round_id), &round);
+
+        env.events().publish((
+            "round_settled",
+            market_id,
+            round_id,
+            round.outcome
+        ), None::<String>);
+
+        Ok(round.status)
+    }
+
+    /// Claim a member's payout or refund from a settled round
+    ///
+    /// Winners receive their pro-rata share of the payout pool. The last
+    /// winner to claim receives the rounding remainder so the pool is paid
+    /// out exactly.
+    pub fn claim_round(env: Env, market_id: u32, round_id: u32, member: Address) -> Result<i128, Error> {
+        member.require_auth();
+
+        let mut round = Self::load_round(&env, market_id, round_id)?;
+        let entry_key = MarketKey::Entries(market_id, round_id, member.clone());
+        let mut entry: RoundEntry = env
+            .storage()
+            .persistent()
+            .get(&entry_key)
+            .ok_or(Error::NoReward)?;
+
+        if entry.claimed {
+            return Err(Error::RewardAlreadyClaimed);
+        }
+
+        let payout = match round.status {
+            RoundStatus::Open => return Err(Error::RoundNotSettled),
+            RoundStatus::Void => entry.amount,
+            RoundStatus::Settled => {
+                if round.outcome != Some(entry.rise) {
+                    return Err(Error::NoReward);
+                }
+                let payout = if round.claims_remaining == 1 {
+                    round.payout_pool - round.claimed
+                } else {
+                    Self::round_share(&round, entry.amount)
+                };
+                round.claimed += payout;
+                round.claims_remaining -= 1;
+                payout
+            }
+        };
+
+        entry.claimed = true;
+        env.storage().persistent().set(&entry_key, &entry);
+        env.storage()
+            .persistent()
+            .set(&MarketKey::Rounds(market_id, round_id), &round);
+
+        Self::pay_out(&env, &member, payout, &TreasuryKey::MarketEntries)?;
+
+        Ok(payout)
+    }
+
+    /// Get market information
+    pub fn get_market(env: Env, market_id: u32) -> Option<Market> {
+        env.storage()
+            .persistent()
+            .get(&MarketKey::Markets(market_id))
+    }
+
+    /// Get round information
+    pub fn get_round(env: Env, market_id: u32, round_id: u32) -> Option<Round> {
+        env.storage()
+            .persistent()
+            .get(&MarketKey::Rounds(market_id, round_id))
+    }
+
+    /// Get a member's entry in a round
+    pub fn get_round_entry(env: Env, market_id: u32, round_id: u32, member: Address) -> Option<RoundEntry> {
+        env.storage()
+            .persistent()
+            .get(&MarketKey::Entries(market_id, round_id, member))
+    }
+}
+
+impl CollaborativeDao {
+    fn load_round(env: &Env, market_id: u32, round_id: u32) -> Result<Round, Error> {
+        env.storage()
+            .persistent()
+            .get(&MarketKey::Rounds(market_id, round_id))
+            .ok_or(Error::RoundNotFound)
+    }
+
+    /// Pro-rata share of the payout pool for a winning stake
+    fn round_share(round: &Round, amount: i128) -> i128 {
+        let winning_pool = if round.outcome == Some(true) {
+            round.rise_pool
+        } else {
+            round.fall_pool
+        };
+        amount * round.payout_pool / winning_pool
+    }
+}
@@ -0,0 +1,277 @@
+use soroban_sdk::{
+    contractimpl, contracttype, Address, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal,
+    Val, Vec,
+};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, DataKey, Error, MemberRole,
+    Prediction, Team, TreasuryKey, RESOLUTION_WINDOW, SUBMISSION_CUTOFF,
+};
+
+/// Storage layout version written by this build
+///
+/// Version 0 is the original layout, before versioning was introduced.
+/// Version 1 adds member roles, per-team stakes, the team registry, the
+/// solvency ledger and numeric prediction IDs with resolution horizons.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Number of fields in the version 0 `Team` layout
+const TEAM_V0_FIELDS: u32 = 3;
+/// Number of fields in the version 0 `Prediction` layout
+const PREDICTION_V0_FIELDS: u32 = 9;
+
+/// Team layout of schema version 0
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TeamV0 {
+    pub name: String,
+    pub members: Vec<Address>,
+    pub total_stake: i128,
+}
+
+/// Prediction layout of schema version 0
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PredictionV0 {
+    pub team_name: String,
+    pub asset: String,
+    pub prediction: bool,
+    pub stake_amount: i128,
+    pub stake_percentage: u32,
+    pub predictor: Address,
+    pub timestamp: u64,
+    pub resolved: bool,
+    pub outcome: Option<bool>,
+}
+
+/// Storage keys of the version 0 string-keyed predictions and per-user stakes
+///
+/// Variant names match the old `DataKey` variants so the keys serialize
+/// identically.
+#[contracttype]
+#[derive(Clone)]
+pub(crate) enum LegacyKey {
+    Predictions(String),
+    UserStakes(Address),
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Replace the contract code with an uploaded wasm (admin only)
+    ///
+    /// Storage is preserved; call `migrate` and `finalize_migration`
+    /// afterwards if the new code bumps the schema version.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+
+        env.events().publish(("contract_upgraded", new_wasm_hash), None::<String>);
+
+        Ok(())
+    }
+
+    /// Rewrite stored teams and predictions into the current layout (admin only)
+    ///
+    /// Entries are passed explicitly so large deployments can migrate in
+    /// batches; predictions are passed by their version 0 string ID.
+    /// Entries already in the current layout are left untouched. Returns the
+    /// number of entries rewritten. The schema version is only recorded by
+    /// `finalize_migration` once every batch is through.
+    pub fn migrate(
+        env: Env,
+        team_names: Vec<String>,
+        prediction_ids: Vec<String>,
+    ) -> Result<u32, Error> {
+        Self::require_admin(&env)?;
+
+        let mut migrated = 0;
+
+        for team_name in team_names.iter() {
+            if Self::migrate_team(&env, &team_name)? {
+                migrated += 1;
+            }
+        }
+
+        for label in prediction_ids.iter() {
+            if Self::migrate_prediction(&env, &label)? {
+                migrated += 1;
+            }
+        }
+
+        env.events().publish(("contract_migrated", CURRENT_SCHEMA_VERSION, migrated), None::<String>);
+
+        Ok(migrated)
+    }
+
+    /// Record that every stored entry is in the current layout (admin only)
+    ///
+    /// Call once the last `migrate` batch has gone through.
+    pub fn finalize_migration(env: Env) -> Result<u32, Error> {
+        Self::require_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::SchemaVersion, &CURRENT_SCHEMA_VERSION);
+
+        env.events().publish(("migration_finalized", CURRENT_SCHEMA_VERSION), None::<String>);
+
+        Ok(CURRENT_SCHEMA_VERSION)
+    }
+
+    /// Get the storage layout version, as recorded by the last finalized
+    /// migration
+    pub fn get_schema_version(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::SchemaVersion)
+            .unwrap_or(0)
+    }
+}
+
+impl CollaborativeDao {
+    /// Read a stored struct as its raw field map so its layout can be inspected
+    ///
+    /// Decoding a struct from a map with a different field count traps in the
+    /// host instead of returning an error, so layouts are told apart by field
+    /// count and names before decoding.
+    fn stored_fields<K: IntoVal<Env, Val>>(
+        env: &Env,
+        key: &K,
+        missing: Error,
+    ) -> Result<(Val, Map<Symbol, Val>), Error> {
+        let raw: Val = env.storage().persistent().get(key).ok_or(missing)?;
+        let fields = Map::<Symbol, Val>::try_from_val(env, &raw).map_err(|_| Error::MigrationFailed)?;
+        Ok((raw, fields))
+    }
+
+    /// Bring a version 0 team up to the current layout
+    ///
+    /// The first member becomes captain and everyone else a predictor, which
+    /// matches what each member could do before roles existed. Members'
+    /// stakes move under the team, the team's stake is counted in the
+    /// solvency ledger and the team is added to the team registry.
+    fn migrate_team(env: &Env, team_name: &String) -> Result<bool, Error> {
+        let key = DataKey::Teams(team_name.clone());
+        let (raw, fields) = Self::stored_fields(env, &key, Error::TeamNotFound)?;
+
+        if fields.contains_key(Symbol::new(env, "roles")) {
+            return Ok(false);
+        }
+        if fields.len() != TEAM_V0_FIELDS {
+            return Err(Error::MigrationFailed);
+        }
+        let legacy = TeamV0::try_from_val(env, &raw).map_err(|_| Error::MigrationFailed)?;
+
+        let mut roles = Map::new(env);
+        for (position, member) in legacy.members.iter().enumerate() {
+            let role = if position == 0 {
+                MemberRole::Captain
+            } else {
+                MemberRole::Predictor
+            };
+            roles.set(member.clone(), role);
+            Self::migrate_member_stake(env, team_name, &member);
+        }
+
+        // Version 0 stake predates the solvency ledger
+        Self::credit(env, &TreasuryKey::Stakes, legacy.total_stake);
+
+        let team = Team {
+            name: legacy.name,
+            members: legacy.members,
+            total_stake: legacy.total_stake,
+            roles,
+        };
+        env.storage().persistent().set(&key, &team);
+        Self::register_team(env, team_name);
+
+        Ok(true)
+    }
+
+    /// Move a member's per-user stake under their team
+    ///
+    /// Per-user entries belong to the team the user is registered with; a
+    /// member whose lookup points at another team is left for that team's
+    /// migration.
+    fn migrate_member_stake(env: &Env, team_name: &String, member: &Address) {
+        let storage = env.storage().persistent();
+        if storage.get::<_, String>(&DataKey::UserTeams(member.clone())).as_ref() != Some(team_name) {
+            return;
+        }
+
+        let stake_key = LegacyKey::UserStakes(member.clone());
+        if let Some(staked) = storage.get::<_, i128>(&stake_key) {
+            storage.set(&DataKey::MemberStakes(team_name.clone(), member.clone()), &staked);
+            storage.remove(&stake_key);
+        }
+    }
+
+    /// Move a version 0 prediction to a freshly allocated numeric ID
+    ///
+    /// The old string ID is kept as the prediction's label.
+    fn migrate_prediction(env: &Env, label: &String) -> Result<bool, Error> {
+        let label_key = DataKey::PredictionLabels(label.clone());
+        if env.storage().persistent().has(&label_key) {
+            return Ok(false);
+        }
+
+        let legacy_key = LegacyKey::Predictions(label.clone());
+        let (raw, fields) = Self::stored_fields(env, &legacy_key, Error::PredictionNotFound)?;
+        if fields.len() != PREDICTION_V0_FIELDS {
+            return Err(Error::MigrationFailed);
+        }
+        let legacy = PredictionV0::try_from_val(env, &raw).map_err(|_| Error::MigrationFailed)?;
+        let prediction = Self::upgrade_prediction_v0(env, legacy)?;
+
+        let prediction_id = Self::generate_prediction_id(env);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Predictions(prediction_id), &prediction);
+        env.storage().persistent().set(&label_key, &prediction_id);
+        env.storage().persistent().remove(&legacy_key);
+
+        Ok(true)
+    }
+
+    /// Fill in the resolution horizon and expiry of a version 0 prediction
+    ///
+    /// Version 0 predictions resolved against the next price round and never
+    /// escrowed their stake, so open ones are locked now to keep the escrow
+    /// ledger consistent when they are resolved or refunded. An open
+    /// prediction whose stake the predictor no longer holds is voided instead,
+    /// as locking it would reserve stake they do not have.
+    fn upgrade_prediction_v0(env: &Env, legacy: PredictionV0) -> Result<Prediction, Error> {
+        let resolve_after = legacy.timestamp + SUBMISSION_CUTOFF;
+        let mut resolved = legacy.resolved;
+        let mut outcome = legacy.outcome;
+
+        if !resolved {
+            // The predictor's stake has to be under the team before it can
+            // be checked
+            Self::migrate_team(env, &legacy.team_name)?;
+            let balance =
+                Self::get_member_balance(env.clone(), legacy.team_name.clone(), legacy.predictor.clone());
+            if balance.available >= legacy.stake_amount {
+                Self::lock_stake(env, &legacy.team_name, &legacy.predictor, legacy.stake_amount);
+            } else {
+                resolved = true;
+                outcome = None;
+            }
+        }
+
+        Ok(Prediction {
+            team_name: legacy.team_name,
+            asset: legacy.asset,
+            prediction: legacy.prediction,
+            stake_amount: legacy.stake_amount,
+            stake_percentage: legacy.stake_percentage,
+            predictor: legacy.predictor,
+            timestamp: legacy.timestamp,
+            resolve_after,
+            expires_at: resolve_after + RESOLUTION_WINDOW,
+            resolved,
+            outcome,
+        })
+    }
+}
@@ -0,0 +1,451 @@
+use soroban_sdk::{contractimpl, contracttype, xdr::ToXdr, Address, Bytes, BytesN, Env, String, Vec};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, DataKey, Error, MemberRole,
+    Prediction, BPS_DENOMINATOR,
+};
+
+/// Leading zero bits a work hash needs until the admin configures a
+/// difficulty, and the least any asset's difficulty can drop to
+pub const DEFAULT_MINING_DIFFICULTY: u32 = 4;
+/// Epochs of difficulty history kept per asset
+pub const MAX_DIFFICULTY_HISTORY: u32 = 30;
+/// Hardest difficulty the admin can configure
+pub const MAX_MINING_DIFFICULTY: u32 = 64;
+/// Bonus boost per difficulty's worth of team work (10% of the base bonus)
+pub const WORK_BOOST_BPS: i128 = 1_000;
+/// Upper bound on the work boost (doubles the base bonus)
+pub const MAX_WORK_BOOST_BPS: i128 = 10_000;
+
+/// Zero bits above this add no further work, keeping work within `i128`
+const MAX_WORK_BITS: u32 = 120;
+
+/// How the mining difficulty of each asset follows the work submitted
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DifficultyConfig {
+    pub epoch_length: u64,       // seconds between difficulty adjustments
+    pub target_submissions: u32, // valid work submissions wanted per epoch
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+}
+
+/// Mining difficulty of an asset during one epoch
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EpochDifficulty {
+    pub epoch: u64, // ledger timestamp divided by the epoch length
+    pub difficulty: u32,
+    pub submissions: u32, // valid work submissions during the epoch
+}
+
+/// Storage keys for team mining
+#[contracttype]
+#[derive(Clone)]
+pub enum MiningKey {
+    /// Best zero bits submitted indexed by prediction ID and member
+    Work(u64, Address),
+    /// Members who submitted work indexed by prediction ID, in submission order
+    Miners(u64),
+    /// Combined work of a prediction's miners indexed by prediction ID
+    TeamWork(u64),
+    /// Difficulty controller settings
+    DifficultyConfig,
+    /// Current epoch's difficulty indexed by asset
+    AssetDifficulty(String),
+    /// Closed epochs indexed by asset, oldest first
+    DifficultyHistory(String),
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Set the leading zero bits a work hash needs on assets nobody mined
+    /// yet (admin only)
+    ///
+    /// Each asset's difficulty then follows its own submissions (see
+    /// `get_difficulty`).
+    pub fn set_mining_difficulty(env: Env, difficulty: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if !(DEFAULT_MINING_DIFFICULTY..=MAX_MINING_DIFFICULTY).contains(&difficulty) {
+            return Err(Error::InvalidDifficulty);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::MiningDifficulty, &difficulty);
+
+        env.events().publish(("mining_difficulty_updated", difficulty), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the leading zero bits a work hash needs on assets nobody mined yet
+    pub fn get_mining_difficulty(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MiningDifficulty)
+            .unwrap_or(DEFAULT_MINING_DIFFICULTY)
+    }
+
+    /// Set how mining difficulty adjusts to the work submitted (admin only)
+    ///
+    /// The difficulty range may not reach below `DEFAULT_MINING_DIFFICULTY`,
+    /// so idle epochs cannot wear an asset's difficulty down to nothing.
+    pub fn set_difficulty_config(env: Env, config: DifficultyConfig) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if config.epoch_length == 0
+            || config.target_submissions == 0
+            || config.min_difficulty < DEFAULT_MINING_DIFFICULTY
+            || config.min_difficulty > config.max_difficulty
+            || config.max_difficulty > MAX_MINING_DIFFICULTY
+        {
+            return Err(Error::InvalidDifficulty);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&MiningKey::DifficultyConfig, &config);
+
+        env.events().publish((
+            "difficulty_config_updated",
+            config.epoch_length,
+            config.target_submissions
+        ), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get how mining difficulty adjusts to the work submitted
+    ///
+    /// Until configured, difficulty adjusts daily towards 24 submissions per
+    /// asset between `DEFAULT_MINING_DIFFICULTY` and `MAX_MINING_DIFFICULTY`.
+    pub fn get_difficulty_config(env: Env) -> DifficultyConfig {
+        env.storage()
+            .persistent()
+            .get(&MiningKey::DifficultyConfig)
+            .unwrap_or(DifficultyConfig {
+                epoch_length: 24 * 60 * 60,
+                target_submissions: 24,
+                min_difficulty: DEFAULT_MINING_DIFFICULTY,
+                max_difficulty: MAX_MINING_DIFFICULTY,
+            })
+    }
+
+    /// Get the leading zero bits a work hash on an asset's predictions needs
+    ///
+    /// At the first submission of an epoch the difficulty rises by one bit if
+    /// the previous epoch saw more than the target submissions and drops by
+    /// one bit if it saw fewer than half of them. Every epoch without any
+    /// submission in between drops it by another bit.
+    pub fn get_difficulty(env: Env, asset: String) -> u32 {
+        Self::asset_epoch(&env, &asset).0.difficulty
+    }
+
+    /// Get an asset's past epochs, oldest first
+    ///
+    /// Keeps the last `MAX_DIFFICULTY_HISTORY` epochs that saw submissions.
+    pub fn get_difficulty_history(env: Env, asset: String) -> Vec<EpochDifficulty> {
+        let mut history: Vec<EpochDifficulty> = env
+            .storage()
+            .persistent()
+            .get(&MiningKey::DifficultyHistory(asset.clone()))
+            .unwrap_or(Vec::new(&env));
+
+        // The stored epoch counts as closed as soon as a new one started
+        if let (_, Some(closed)) = Self::asset_epoch(&env, &asset) {
+            Self::push_history(&mut history, closed);
+        }
+
+        history
+    }
+
+    /// Submit a member's nonce to their team's mining round on a prediction
+    ///
+    /// The round is open from `resolve_after` until the prediction is
+    /// resolved or expires. Only each member's best hash counts; returns the
+    /// member's best zero bits so far.
+    pub fn submit_work(env: Env, prediction_id: u64, member: Address, nonce: u64) -> Result<u32, Error> {
+        member.require_auth();
+
+        let prediction = Self::load_prediction(&env, prediction_id)?;
+        Self::add_work(&env, prediction_id, &prediction, &member, nonce)
+    }
+
+    /// Get the work hash a nonce produces for a prediction and miner
+    ///
+    /// Available once both oracle prices of the prediction are, so members
+    /// can search for a nonce before submitting or resolving. Resolution
+    /// hashes with the predictor as miner.
+    pub fn get_work_hash(env: Env, prediction_id: u64, miner: Address, nonce: u64) -> Result<BytesN<32>, Error> {
+        let prediction = Self::load_prediction(&env, prediction_id)?;
+        let (start, end) = Self::prediction_prices(&env, &prediction)?;
+
+        Ok(Self::work_hash(&env, prediction_id, &prediction, &miner, start.price, end.price, nonce))
+    }
+
+    /// Get a member's best zero bits in a prediction's mining round
+    pub fn get_member_work(env: Env, prediction_id: u64, member: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&MiningKey::Work(prediction_id, member))
+            .unwrap_or(0)
+    }
+
+    /// Get the combined work of a prediction's miners, in expected hashes
+    pub fn get_team_work(env: Env, prediction_id: u64) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&MiningKey::TeamWork(prediction_id))
+            .unwrap_or(0)
+    }
+
+    /// Get the members who submitted work on a prediction
+    pub fn get_miners(env: Env, prediction_id: u64) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&MiningKey::Miners(prediction_id))
+            .unwrap_or(Vec::new(&env))
+    }
+}
+
+impl CollaborativeDao {
+    /// Record the reward of a winning prediction
+    ///
+    /// The predictor gets their stake back with the base bonus. The team's
+    /// mining work raises the bonus by `WORK_BOOST_BPS` for every
+    /// difficulty's worth of work, and that boost is shared among the miners
+    /// pro-rata to their work. The whole bonus is drawn from the reward pool.
+    pub(crate) fn reward_winners(env: &Env, prediction_id: u64, prediction: &Prediction) {
+        let base_bonus = prediction.stake_amount / 10;
+        let difficulty = Self::get_difficulty(env.clone(), prediction.asset.clone());
+        let difficulty_work = Self::work_for_bits(difficulty);
+        let team_work = Self::get_team_work(env.clone(), prediction_id);
+        let boost_bps = (team_work / difficulty_work)
+            .min(MAX_WORK_BOOST_BPS / WORK_BOOST_BPS)
+            * WORK_BOOST_BPS;
+        let boost = base_bonus * boost_bps / BPS_DENOMINATOR;
+
+        let paid = Self::draw_bonus(env, base_bonus + boost);
+        let miners_share = if boost > 0 {
+            paid * boost / (base_bonus + boost)
+        } else {
+            0
+        };
+        Self::record_reward(
+            env,
+            prediction_id,
+            &prediction.predictor,
+            prediction.stake_amount + paid - miners_share,
+        );
+
+        // The last miner receives the rounding remainder
+        let miners = Self::get_miners(env.clone(), prediction_id);
+        let mut remaining = miners_share;
+        for (position, miner) in miners.iter().enumerate() {
+            let share = if position as u32 + 1 == miners.len() {
+                remaining
+            } else {
+                let bits = Self::get_member_work(env.clone(), prediction_id, miner.clone());
+                miners_share * Self::work_for_bits(bits) / team_work
+            };
+            if share > 0 {
+                Self::record_reward(env, prediction_id, &miner, share);
+                remaining -= share;
+            }
+        }
+    }
+
+    /// Keccak-256 over the team name, prediction ID, miner, both oracle
+    /// prices and the nonce
+    ///
+    /// Integers are big-endian; the team name and miner are XDR encoded.
+    pub(crate) fn work_hash(
+        env: &Env,
+        prediction_id: u64,
+        prediction: &Prediction,
+        miner: &Address,
+        start_price: i128,
+        end_price: i128,
+        nonce: u64,
+    ) -> BytesN<32> {
+        let mut input = Bytes::new(env);
+        input.append(&prediction.team_name.clone().to_xdr(env));
+        input.extend_from_array(&prediction_id.to_be_bytes());
+        input.append(&miner.clone().to_xdr(env));
+        input.extend_from_array(&start_price.to_be_bytes());
+        input.extend_from_array(&end_price.to_be_bytes());
+        input.extend_from_array(&nonce.to_be_bytes());
+
+        env.crypto().keccak256(&input).into()
+    }
+
+    /// Add a member's nonce to a prediction's mining round and return the
+    /// member's best zero bits so far
+    pub(crate) fn add_work(
+        env: &Env,
+        prediction_id: u64,
+        prediction: &Prediction,
+        member: &Address,
+        nonce: u64,
+    ) -> Result<u32, Error> {
+        if prediction.resolved {
+            return Err(Error::AlreadyResolved);
+        }
+        let now = env.ledger().timestamp();
+        if now < prediction.resolve_after {
+            return Err(Error::ResolutionTooEarly);
+        }
+        if now > prediction.expires_at {
+            return Err(Error::PredictionExpired);
+        }
+
+        Self::require_role(env, &prediction.team_name, member, MemberRole::Staker)?;
+
+        let (start, end) = Self::prediction_prices(env, prediction)?;
+        let hash = Self::work_hash(env, prediction_id, prediction, member, start.price, end.price, nonce);
+        let zero_bits = Self::leading_zero_bits(&hash);
+        if zero_bits < Self::get_difficulty(env.clone(), prediction.asset.clone()) {
+            return Err(Error::HashVerificationFailed);
+        }
+        Self::record_submission(env, &prediction.asset);
+
+        let work_key = MiningKey::Work(prediction_id, member.clone());
+        let best: Option<u32> = env.storage().persistent().get(&work_key);
+        match best {
+            Some(best) if best >= zero_bits => return Ok(best),
+            Some(best) => {
+                Self::add_team_work(env, prediction_id, Self::work_for_bits(zero_bits) - Self::work_for_bits(best));
+            }
+            None => {
+                let mut miners = Self::get_miners(env.clone(), prediction_id);
+                miners.push_back(member.clone());
+                env.storage()
+                    .persistent()
+                    .set(&MiningKey::Miners(prediction_id), &miners);
+                Self::add_team_work(env, prediction_id, Self::work_for_bits(zero_bits));
+            }
+        }
+        env.storage().persistent().set(&work_key, &zero_bits);
+
+        env.events().publish(("work_submitted", prediction_id, member.clone(), zero_bits), None::<String>);
+
+        Ok(zero_bits)
+    }
+
+    /// Count a valid work submission towards its asset's current epoch
+    ///
+    /// Closes the stored epoch into the history first if a new one started.
+    pub(crate) fn record_submission(env: &Env, asset: &String) {
+        let (mut current, closed) = Self::asset_epoch(env, asset);
+
+        if let Some(closed) = closed {
+            let history_key = MiningKey::DifficultyHistory(asset.clone());
+            let mut history = env
+                .storage()
+                .persistent()
+                .get(&history_key)
+                .unwrap_or(Vec::new(env));
+            Self::push_history(&mut history, closed.clone());
+            env.storage().persistent().set(&history_key, &history);
+
+            if current.difficulty != closed.difficulty {
+                env.events().publish((
+                    "difficulty_adjusted",
+                    asset.clone(),
+                    closed.difficulty,
+                    current.difficulty
+                ), None::<String>);
+            }
+        }
+
+        current.submissions += 1;
+        env.storage()
+            .persistent()
+            .set(&MiningKey::AssetDifficulty(asset.clone()), &current);
+    }
+
+    /// An asset's epoch as of now, along with the stored epoch if that one
+    /// has ended
+    fn asset_epoch(env: &Env, asset: &String) -> (EpochDifficulty, Option<EpochDifficulty>) {
+        let config = Self::get_difficulty_config(env.clone());
+        let epoch = env.ledger().timestamp() / config.epoch_length;
+        let clamp = |difficulty: u32| difficulty.clamp(config.min_difficulty, config.max_difficulty);
+
+        let stored: Option<EpochDifficulty> = env
+            .storage()
+            .persistent()
+            .get(&MiningKey::AssetDifficulty(asset.clone()));
+        match stored {
+            None => (
+                EpochDifficulty {
+                    epoch,
+                    difficulty: clamp(Self::get_mining_difficulty(env.clone())),
+                    submissions: 0,
+                },
+                None,
+            ),
+            Some(stored) if epoch <= stored.epoch => (stored, None),
+            Some(stored) => {
+                let target = config.target_submissions;
+                let mut difficulty = if stored.submissions > target {
+                    stored.difficulty + 1
+                } else if stored.submissions.saturating_mul(2) < target {
+                    stored.difficulty.saturating_sub(1)
+                } else {
+                    stored.difficulty
+                };
+                let idle_epochs = epoch - stored.epoch - 1;
+                difficulty = difficulty.saturating_sub(idle_epochs.min(u32::MAX as u64) as u32);
+
+                (
+                    EpochDifficulty {
+                        epoch,
+                        difficulty: clamp(difficulty),
+                        submissions: 0,
+                    },
+                    Some(stored),
+                )
+            }
+        }
+    }
+
+    fn push_history(history: &mut Vec<EpochDifficulty>, epoch: EpochDifficulty) {
+        history.push_back(epoch);
+        while history.len() > MAX_DIFFICULTY_HISTORY {
+            history.pop_front();
+        }
+    }
+
+    /// Expected number of hashes needed to find `bits` leading zero bits
+    pub(crate) fn work_for_bits(bits: u32) -> i128 {
+        1i128 << bits.min(MAX_WORK_BITS)
+    }
+
+    fn add_team_work(env: &Env, prediction_id: u64, work: i128) {
+        let team_work = Self::get_team_work(env.clone(), prediction_id);
+        env.storage()
+            .persistent()
+            .set(&MiningKey::TeamWork(prediction_id), &(team_work + work));
+    }
+
+    pub(crate) fn load_prediction(env: &Env, prediction_id: u64) -> Result<Prediction, Error> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Predictions(prediction_id))
+            .ok_or(Error::PredictionNotFound)
+    }
+
+    /// Number of leading zero bits of a hash
+    pub(crate) fn leading_zero_bits(hash: &BytesN<32>) -> u32 {
+        let mut bits = 0;
+        for byte in hash.to_array() {
+            bits += byte.leading_zeros();
+            if byte != 0 {
+                break;
+            }
+        }
+        bits
+    }
+}
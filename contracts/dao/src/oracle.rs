@@ -0,0 +1,146 @@
+use soroban_sdk::{contractimpl, contracttype, Address, Env, String};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, DataKey, Error, PriceData,
+    Prediction, ReflectorClient, BPS_DENOMINATOR,
+};
+
+/// Furthest a price may lie from the time it was requested for until the
+/// admin configures an asset (15 minutes)
+pub const DEFAULT_MAX_STALENESS: u64 = 15 * 60;
+/// Largest price move a prediction resolves on until the admin configures an
+/// asset (50%)
+pub const DEFAULT_MAX_DEVIATION_BPS: u32 = 5_000;
+
+/// Sanity limits on the oracle prices a prediction resolves on
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OracleGuard {
+    pub max_staleness: u64,     // seconds between a price and the time it was requested for
+    pub max_deviation_bps: u32, // move between start and horizon price that trips the breaker
+}
+
+/// Storage keys for oracle guards
+#[contracttype]
+#[derive(Clone)]
+pub enum OracleKey {
+    /// Oracle guard indexed by asset
+    Guards(String),
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Set the oracle guard of an asset (admin only)
+    ///
+    /// A zero `max_staleness` only accepts prices of exactly the requested
+    /// time, and a zero `max_deviation_bps` halts resolution of any price
+    /// move on the asset.
+    pub fn set_oracle_guard(env: Env, asset: String, guard: OracleGuard) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&OracleKey::Guards(asset.clone()), &guard);
+
+        env.events().publish((
+            "oracle_guard_updated",
+            asset,
+            guard.max_staleness,
+            guard.max_deviation_bps
+        ), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the oracle guard of an asset
+    pub fn get_oracle_guard(env: Env, asset: String) -> OracleGuard {
+        env.storage()
+            .persistent()
+            .get(&OracleKey::Guards(asset))
+            .unwrap_or(OracleGuard {
+                max_staleness: DEFAULT_MAX_STALENESS,
+                max_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
+            })
+    }
+
+    /// Get the latest Reflector price of an asset
+    ///
+    /// Lets predictors see the price a new prediction starts from.
+    pub fn get_last_price(env: Env, asset: String) -> Result<PriceData, Error> {
+        let reflector_address: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ReflectorOracle)
+            .ok_or(Error::OracleDataUnavailable)?;
+        ReflectorClient::new(&env, &reflector_address).get_last_price(&env, asset)
+    }
+
+    /// Get the number of decimals Reflector prices are scaled by
+    pub fn get_oracle_decimals(env: Env) -> Result<u32, Error> {
+        let reflector_address: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ReflectorOracle)
+            .ok_or(Error::OracleDataUnavailable)?;
+        Ok(ReflectorClient::new(&env, &reflector_address).get_decimals(&env))
+    }
+}
+
+impl CollaborativeDao {
+    /// Check the start and horizon prices of a prediction before resolving
+    /// on them
+    ///
+    /// Each price must lie within both Reflector's resolution and the
+    /// asset's `max_staleness` of the time it was requested for, and the move
+    /// between them must not exceed `max_deviation_bps`.
+    pub(crate) fn check_prices(
+        env: &Env,
+        prediction_id: u64,
+        prediction: &Prediction,
+        start: &PriceData,
+        end: &PriceData,
+    ) -> Result<(), Error> {
+        if start.price <= 0 || end.price <= 0 {
+            env.events().publish((
+                "oracle_data_invalid",
+                prediction_id,
+                end.price,
+                start.price
+            ), None::<String>);
+            return Err(Error::OracleDataUnavailable);
+        }
+
+        let reflector_address: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ReflectorOracle)
+            .ok_or(Error::OracleDataUnavailable)?;
+        let resolution = ReflectorClient::new(env, &reflector_address).get_resolution(env) as u64;
+        let guard = Self::get_oracle_guard(env.clone(), prediction.asset.clone());
+
+        for (requested, data) in [(prediction.timestamp, start), (prediction.resolve_after, end)] {
+            let lag = requested.abs_diff(data.timestamp);
+            if lag > resolution || lag > guard.max_staleness {
+                env.events().publish((
+                    "oracle_price_stale",
+                    prediction_id,
+                    requested,
+                    data.timestamp
+                ), None::<String>);
+                return Err(Error::StalePrice);
+            }
+        }
+
+        let deviation_bps = (end.price - start.price).abs() * BPS_DENOMINATOR / start.price;
+        if deviation_bps > guard.max_deviation_bps as i128 {
+            env.events().publish((
+                "oracle_price_deviation",
+                prediction_id,
+                deviation_bps
+            ), None::<String>);
+            return Err(Error::PriceDeviation);
+        }
+
+        Ok(())
+    }
+}
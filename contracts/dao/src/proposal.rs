@@ -0,0 +1,327 @@
+use soroban_sdk::{contractimpl, contracttype, Address, Env, Map, String};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, Error, MemberRole,
+    BPS_DENOMINATOR, SUBMISSION_CUTOFF,
+};
+
+/// Voting rules a team applies to prediction proposals
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VotingConfig {
+    pub quorum_bps: u32,    // share of the team's stake that must vote
+    pub threshold_bps: u32, // share of the votes cast that approvals must exceed
+    pub voting_period: u64, // seconds a proposal stays open
+}
+
+/// Lifecycle of a prediction proposal
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProposalStatus {
+    /// Collecting votes until `voting_deadline`
+    Pending,
+    /// Reached quorum and threshold; the prediction is live
+    Approved,
+    /// Can no longer reach the threshold; the stake is released
+    Rejected,
+    /// Deadline passed without a decision; the stake is released
+    Expired,
+}
+
+/// A prediction awaiting the team's approval
+///
+/// The proposer's stake is escrowed when proposing, so the prediction can
+/// go live as soon as the vote passes. Votes are weighed against the
+/// members' stakes at proposal time, so stake moved during the vote cannot
+/// be counted twice.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub team_name: String,
+    pub proposer: Address,
+    pub asset: String,
+    pub prediction: bool, // true = price will rise, false = price will fall
+    pub stake_amount: i128,
+    pub stake_percentage: u32,
+    pub resolve_after: u64,
+    pub voting_deadline: u64,
+    pub team_stake: i128,            // team's total stake when proposed
+    pub weights: Map<Address, i128>, // each member's stake when proposed
+    pub approve_weight: i128,
+    pub reject_weight: i128,
+    pub status: ProposalStatus,
+    pub prediction_id: Option<u64>, // set once approved
+}
+
+/// Storage keys for proposals
+#[contracttype]
+#[derive(Clone)]
+pub enum ProposalKey {
+    /// Last proposal ID handed out
+    ProposalCounter,
+    /// Proposal data indexed by proposal ID
+    Proposals(u64),
+    /// Vote cast indexed by proposal ID and member (true = approve)
+    Votes(u64, Address),
+    /// Voting rules indexed by team name
+    TeamVoting(String),
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Propose a prediction for the team to vote on and return the proposal ID
+    ///
+    /// The stake is escrowed from the proposer right away and the proposer's
+    /// own stake counts as an approval. The vote must be able to finish
+    /// `SUBMISSION_CUTOFF` seconds before `resolve_after`.
+    pub fn propose_prediction(
+        env: Env,
+        team_name: String,
+        proposer: Address,
+        asset: String,
+        prediction: bool,
+        stake_amount: i128,
+        stake_percentage: u32,
+        resolve_after: u64,
+    ) -> Result<u64, Error> {
+        proposer.require_auth();
+
+        Self::validate_resolution_time(&env, resolve_after)?;
+
+        let config = Self::get_voting_config(env.clone(), team_name.clone());
+        let voting_deadline = env.ledger().timestamp() + config.voting_period;
+        if voting_deadline + SUBMISSION_CUTOFF > resolve_after {
+            return Err(Error::SubmissionClosed);
+        }
+
+        if stake_amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
+
+        let team = Self::require_role(&env, &team_name, &proposer, MemberRole::Predictor)?;
+        Self::escrow_stake(&env, &team_name, &proposer, stake_amount)?;
+
+        let mut weights = Map::new(&env);
+        for member in team.members.iter() {
+            let stake = Self::get_member_stake(env.clone(), team_name.clone(), member.clone());
+            if stake > 0 {
+                weights.set(member, stake);
+            }
+        }
+
+        let proposal_id: u64 = env
+            .storage()
+            .persistent()
+            .get(&ProposalKey::ProposalCounter)
+            .unwrap_or(0)
+            + 1;
+        env.storage()
+            .persistent()
+            .set(&ProposalKey::ProposalCounter, &proposal_id);
+
+        let mut proposal = Proposal {
+            team_name: team_name.clone(),
+            proposer: proposer.clone(),
+            asset,
+            prediction,
+            stake_amount,
+            stake_percentage,
+            resolve_after,
+            voting_deadline,
+            team_stake: team.total_stake,
+            weights,
+            approve_weight: 0,
+            reject_weight: 0,
+            status: ProposalStatus::Pending,
+            prediction_id: None,
+        };
+
+        env.events().publish(("prediction_proposed", team_name, proposal_id), None::<String>);
+
+        Self::cast_vote(&env, proposal_id, &mut proposal, &config, &proposer, true)?;
+
+        Ok(proposal_id)
+    }
+
+    /// Vote on a pending proposal with the voter's stake in the team when
+    /// the proposal was made
+    ///
+    /// The proposal goes live as a prediction as soon as it reaches quorum
+    /// and threshold, and is rejected once approvals can no longer exceed the
+    /// threshold even if every remaining member approves.
+    pub fn vote_prediction(
+        env: Env,
+        proposal_id: u64,
+        voter: Address,
+        approve: bool,
+    ) -> Result<ProposalStatus, Error> {
+        voter.require_auth();
+
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+        if proposal.status != ProposalStatus::Pending
+            || env.ledger().timestamp() > proposal.voting_deadline
+        {
+            return Err(Error::ProposalClosed);
+        }
+
+        Self::require_role(&env, &proposal.team_name, &voter, MemberRole::Staker)?;
+        if env
+            .storage()
+            .persistent()
+            .has(&ProposalKey::Votes(proposal_id, voter.clone()))
+        {
+            return Err(Error::AlreadyVoted);
+        }
+
+        let config = Self::get_voting_config(env.clone(), proposal.team_name.clone());
+        Self::cast_vote(&env, proposal_id, &mut proposal, &config, &voter, approve)?;
+
+        Ok(proposal.status)
+    }
+
+    /// Close a proposal whose voting deadline passed without a decision
+    ///
+    /// Releases the escrowed stake back to the proposer.
+    pub fn close_proposal(env: Env, proposal_id: u64) -> Result<(), Error> {
+        let mut proposal = Self::load_proposal(&env, proposal_id)?;
+        if proposal.status != ProposalStatus::Pending {
+            return Err(Error::ProposalClosed);
+        }
+        if env.ledger().timestamp() <= proposal.voting_deadline {
+            return Err(Error::VotingNotEnded);
+        }
+
+        Self::release_stake(&env, &proposal.team_name, &proposal.proposer, proposal.stake_amount);
+        proposal.status = ProposalStatus::Expired;
+        env.storage()
+            .persistent()
+            .set(&ProposalKey::Proposals(proposal_id), &proposal);
+
+        env.events().publish(("proposal_expired", proposal.team_name, proposal_id), None::<String>);
+
+        Ok(())
+    }
+
+    /// Set the team's voting rules for new votes (captain only)
+    pub fn set_voting_config(
+        env: Env,
+        team_name: String,
+        captain: Address,
+        config: VotingConfig,
+    ) -> Result<(), Error> {
+        captain.require_auth();
+
+        Self::require_role(&env, &team_name, &captain, MemberRole::Captain)?;
+        if config.quorum_bps == 0
+            || config.quorum_bps as i128 > BPS_DENOMINATOR
+            || config.threshold_bps as i128 >= BPS_DENOMINATOR
+            || config.voting_period == 0
+        {
+            return Err(Error::InvalidVotingConfig);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&ProposalKey::TeamVoting(team_name), &config);
+
+        Ok(())
+    }
+
+    /// Get a team's voting rules
+    ///
+    /// Teams that never configured voting need a majority of the votes with
+    /// half of the team's stake voting within an hour.
+    pub fn get_voting_config(env: Env, team_name: String) -> VotingConfig {
+        env.storage()
+            .persistent()
+            .get(&ProposalKey::TeamVoting(team_name))
+            .unwrap_or(VotingConfig {
+                quorum_bps: 5_000,
+                threshold_bps: 5_000,
+                voting_period: 60 * 60,
+            })
+    }
+
+    /// Get proposal information
+    pub fn get_proposal(env: Env, proposal_id: u64) -> Option<Proposal> {
+        env.storage()
+            .persistent()
+            .get(&ProposalKey::Proposals(proposal_id))
+    }
+
+    /// Get a member's vote on a proposal (true = approve)
+    pub fn get_vote(env: Env, proposal_id: u64, voter: Address) -> Option<bool> {
+        env.storage()
+            .persistent()
+            .get(&ProposalKey::Votes(proposal_id, voter))
+    }
+}
+
+impl CollaborativeDao {
+    fn load_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, Error> {
+        env.storage()
+            .persistent()
+            .get(&ProposalKey::Proposals(proposal_id))
+            .ok_or(Error::ProposalNotFound)
+    }
+
+    /// Record a vote weighted by the voter's stake at proposal time and apply
+    /// its outcome
+    fn cast_vote(
+        env: &Env,
+        proposal_id: u64,
+        proposal: &mut Proposal,
+        config: &VotingConfig,
+        voter: &Address,
+        approve: bool,
+    ) -> Result<(), Error> {
+        let weight = proposal.weights.get(voter.clone()).unwrap_or(0);
+        if weight <= 0 {
+            return Err(Error::NoVotingPower);
+        }
+
+        if approve {
+            proposal.approve_weight += weight;
+        } else {
+            proposal.reject_weight += weight;
+        }
+        env.storage()
+            .persistent()
+            .set(&ProposalKey::Votes(proposal_id, voter.clone()), &approve);
+
+        let team_stake = proposal.team_stake;
+        let quorum = config.quorum_bps as i128;
+        let threshold = config.threshold_bps as i128;
+        let votes_cast = proposal.approve_weight + proposal.reject_weight;
+
+        if votes_cast * BPS_DENOMINATOR >= quorum * team_stake
+            && proposal.approve_weight * BPS_DENOMINATOR > threshold * votes_cast
+        {
+            let prediction_id = Self::open_prediction(
+                env,
+                proposal.team_name.clone(),
+                proposal.asset.clone(),
+                proposal.prediction,
+                proposal.stake_amount,
+                proposal.stake_percentage,
+                proposal.proposer.clone(),
+                proposal.resolve_after,
+            );
+            proposal.status = ProposalStatus::Approved;
+            proposal.prediction_id = Some(prediction_id);
+
+            env.events().publish(("proposal_approved", proposal_id, prediction_id), None::<String>);
+        } else if proposal.reject_weight * BPS_DENOMINATOR >= (BPS_DENOMINATOR - threshold) * team_stake {
+            Self::release_stake(env, &proposal.team_name, &proposal.proposer, proposal.stake_amount);
+            proposal.status = ProposalStatus::Rejected;
+
+            env.events().publish(("proposal_rejected", proposal_id), None::<String>);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&ProposalKey::Proposals(proposal_id), proposal);
+
+        Ok(())
+    }
+}
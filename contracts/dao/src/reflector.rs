@@ -1,4 +1,6 @@
-use soroban_sdk::{Address, Env, String, Symbol, contracttype};
+use soroban_sdk::{contracttype, symbol_short, vec, Address, Env, IntoVal, String, Symbol};
+
+use crate::Error;
 
 /// Price data structure from Reflector oracle
 #[contracttype]
@@ -22,13 +24,13 @@ pub enum ReflectorAsset {
     Other(Symbol),
 }
 
+/// Maximum length of a Reflector ticker symbol
+const MAX_TICKER_LEN: usize = 32;
+
 /// Reflector client wrapper for easy integration with DAO contract
-/// Updated to use proper Reflector interface for EUR/USD queries
-/// 
-/// MVP Implementation Note: Currently returns mock data to prevent 
-/// UnreachableCodeReached errors during contract execution. In production,
-/// this would make actual contract calls to Reflector oracle.
-#[allow(dead_code)]
+///
+/// Calls the SEP-40 interface exposed by the Reflector oracle contract
+/// (`lastprice`, `price`, `decimals`, `resolution`) via `env.invoke_contract`.
 pub struct ReflectorClient {
     pub address: Address,
 }
@@ -41,85 +43,62 @@ impl ReflectorClient {
     }
 
     /// Fetch the last price for an asset from Reflector oracle
-    /// Uses ReflectorAsset::Other(Symbol::new("EUR")) for EUR/USD pair
-    /// For MVP, returns mock data to prevent contract call errors
-    pub fn get_last_price(&self, env: &Env, asset_symbol: String) -> Option<PriceData> {
-        // Convert asset symbol to ReflectorAsset format
-        let eur_str = String::from_str(env, "EUR");
-        let usd_str = String::from_str(env, "USD");
-        let eur_usd_str = String::from_str(env, "EUR/USD");
-        let btc_str = String::from_str(env, "BTC");
-
-        // For MVP, we'll simulate the ReflectorAsset creation but return mock data
-        // to avoid UnreachableCodeReached errors during contract execution
-        let _reflector_asset = if asset_symbol == eur_str {
-            ReflectorAsset::Other(Symbol::new(env, "EUR"))
-        } else if asset_symbol == usd_str {
-            ReflectorAsset::Other(Symbol::new(env, "USD"))
-        } else if asset_symbol == btc_str {
-            ReflectorAsset::Other(Symbol::new(env, "BTC"))
-        } else {
-            ReflectorAsset::Other(Symbol::new(env, "OTHER"))
-        };
+    /// Pairs such as "EUR/USD" are quoted by their base ticker, ReflectorAsset::Other("EUR")
+    pub fn get_last_price(&self, env: &Env, asset_symbol: String) -> Result<PriceData, Error> {
+        let asset = Self::to_reflector_asset(env, &asset_symbol)?;
 
-        // TODO: In production, make actual Reflector contract call:
-        // let result = env.invoke_contract(
-        //     &self.address,
-        //     &Symbol::new(env, "lastprice"),
-        //     (reflector_asset,).into_val(env)
-        // );
-
-        // For MVP testing, return realistic mock data to prevent execution errors
-        if asset_symbol == eur_str || asset_symbol == eur_usd_str {
-            Some(PriceData {
-                price: 1_085_600_000_000_000i128, // 1.0856 EUR/USD in 14 decimals
-                timestamp: env.ledger().timestamp(),
-            })
-        } else if asset_symbol == usd_str {
-            Some(PriceData {
-                price: 1_000_000_000_000_000i128, // 1.0000 USD baseline
-                timestamp: env.ledger().timestamp(),
-            })
-        } else if asset_symbol == btc_str {
-            Some(PriceData {
-                price: 43_250_750_000_000_000_000i128, // ~43,250.75 BTC/USD
-                timestamp: env.ledger().timestamp(),
-            })
-        } else {
-            Some(PriceData {
-                price: 1_000_000_000_000_000i128, // Default 1.0 price
-                timestamp: env.ledger().timestamp(),
-            })
-        }
+        let result: Option<PriceData> = env.invoke_contract(
+            &self.address,
+            &symbol_short!("lastprice"),
+            vec![env, asset.into_val(env)],
+        );
+
+        result.ok_or(Error::OracleDataUnavailable)
     }
-    
+
     /// Get price at specific timestamp
-    /// For MVP, returns mock historical price with realistic variation
-    pub fn get_price(&self, env: &Env, asset_symbol: String, _timestamp: u64) -> Option<PriceData> {
-        // Get current price and apply small historical variation
-        if let Some(current_data) = self.get_last_price(env, asset_symbol) {
-            // Simulate historical price being slightly different (0.1-0.5% variation)
-            let variation_factor = 9950i128; // 99.5% of current price
-            let historical_price = (current_data.price * variation_factor) / 10000i128;
-            
-            Some(PriceData {
-                price: historical_price,
-                timestamp: _timestamp,
-            })
-        } else {
-            None
-        }
+    pub fn get_price(&self, env: &Env, asset_symbol: String, timestamp: u64) -> Result<PriceData, Error> {
+        let asset = Self::to_reflector_asset(env, &asset_symbol)?;
+
+        let result: Option<PriceData> = env.invoke_contract(
+            &self.address,
+            &symbol_short!("price"),
+            vec![env, asset.into_val(env), timestamp.into_val(env)],
+        );
+
+        result.ok_or(Error::OracleDataUnavailable)
     }
-    
+
     /// Get oracle decimals for proper price scaling
-    #[allow(dead_code)]
-    pub fn get_decimals(&self, _env: &Env) -> u32 {
-        14 // Standard Reflector decimals (10^14)
+    pub fn get_decimals(&self, env: &Env) -> u32 {
+        env.invoke_contract(&self.address, &symbol_short!("decimals"), vec![env])
     }
 
-    /// Convert price from Reflector format to human-readable decimal
-    #[allow(dead_code)]
-    pub fn price_to_decimal(&self, price: i128) -> f64 {
-        (price as f64) / 1e14
+    /// Get oracle update resolution (seconds between price rounds)
+    pub fn get_resolution(&self, env: &Env) -> u32 {
+        env.invoke_contract(&self.address, &Symbol::new(env, "resolution"), vec![env])
+    }
+
+    /// Map an asset string ("EUR", "EUR/USD", "BTC") to the Reflector asset of its base ticker
+    fn to_reflector_asset(env: &Env, asset_symbol: &String) -> Result<ReflectorAsset, Error> {
+        let len = asset_symbol.len() as usize;
+        if len == 0 || len > MAX_TICKER_LEN {
+            return Err(Error::OracleDataUnavailable);
+        }
+
+        let mut buf = [0u8; MAX_TICKER_LEN];
+        asset_symbol.copy_into_slice(&mut buf[..len]);
+
+        let base = match buf[..len].iter().position(|b| *b == b'/') {
+            Some(separator) => &buf[..separator],
+            None => &buf[..len],
+        };
+
+        if base.is_empty() || !base.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_') {
+            return Err(Error::OracleDataUnavailable);
+        }
+
+        let ticker = core::str::from_utf8(base).map_err(|_| Error::OracleDataUnavailable)?;
+        Ok(ReflectorAsset::Other(Symbol::new(env, ticker)))
     }
 }
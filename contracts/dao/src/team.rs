@@ -0,0 +1,450 @@
+use soroban_sdk::{contractimpl, contracttype, Address, Env, String, Vec};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, DataKey, Error, ProposalKey,
+    Team, BPS_DENOMINATOR,
+};
+
+/// Time an invitation stays open before it can no longer be accepted (7 days)
+pub const INVITE_TTL: u64 = 7 * 24 * 60 * 60;
+
+/// Rights of a team member, each including those of the roles before it
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum MemberRole {
+    /// Can stake KALE with the team
+    Staker,
+    /// Can also make predictions and enter market rounds for the team
+    Predictor,
+    /// Can also manage members, roles and invitations; one per team
+    Captain,
+}
+
+/// A member's stake and its share of the team's total stake
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemberShare {
+    pub member: Address,
+    pub role: MemberRole,
+    pub staked: i128,
+    pub locked: i128,
+    pub share_bps: u32, // staked / total_stake in basis points, rounded down
+}
+
+/// Pending invitation to join a team
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Invite {
+    pub inviter: Address,
+    pub expires_at: u64,
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Invite an address to join the inviter's team (captain only)
+    ///
+    /// Inviting again refreshes the expiry of a pending invitation.
+    pub fn invite_member(
+        env: Env,
+        team_name: String,
+        inviter: Address,
+        invitee: Address,
+    ) -> Result<(), Error> {
+        inviter.require_auth();
+
+        let team = Self::require_role(&env, &team_name, &inviter, MemberRole::Captain)?;
+        if team.members.contains(&invitee) {
+            return Err(Error::AlreadyInTeam);
+        }
+
+        Self::write_invite(&env, &team_name, &inviter, &invitee);
+
+        Ok(())
+    }
+
+    /// Join a team as a predictor by accepting a pending invitation
+    pub fn accept_invite(env: Env, team_name: String, invitee: Address) -> Result<(), Error> {
+        invitee.require_auth();
+
+        let invite = Self::get_invite(env.clone(), team_name.clone(), invitee.clone())
+            .ok_or(Error::InviteNotFound)?;
+        if env.ledger().timestamp() > invite.expires_at {
+            return Err(Error::InviteExpired);
+        }
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::UserTeams(invitee.clone()))
+        {
+            return Err(Error::AlreadyInTeam);
+        }
+
+        let mut team: Team = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Teams(team_name.clone()))
+            .ok_or(Error::TeamNotFound)?;
+        team.members.push_back(invitee.clone());
+        team.roles.set(invitee.clone(), MemberRole::Predictor);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team_name.clone()), &team);
+        env.storage()
+            .persistent()
+            .set(&DataKey::UserTeams(invitee.clone()), &team_name);
+
+        Self::remove_invite(&env, &team_name, &invitee);
+
+        env.events().publish(("member_joined", team_name, invitee), None::<String>);
+
+        Ok(())
+    }
+
+    /// Turn down a pending invitation
+    pub fn decline_invite(env: Env, team_name: String, invitee: Address) -> Result<(), Error> {
+        invitee.require_auth();
+
+        if Self::get_invite(env.clone(), team_name.clone(), invitee.clone()).is_none() {
+            return Err(Error::InviteNotFound);
+        }
+        Self::remove_invite(&env, &team_name, &invitee);
+
+        Ok(())
+    }
+
+    /// Withdraw a pending invitation (captain only)
+    pub fn revoke_invite(
+        env: Env,
+        team_name: String,
+        member: Address,
+        invitee: Address,
+    ) -> Result<(), Error> {
+        member.require_auth();
+
+        Self::require_role(&env, &team_name, &member, MemberRole::Captain)?;
+        if Self::get_invite(env.clone(), team_name.clone(), invitee.clone()).is_none() {
+            return Err(Error::InviteNotFound);
+        }
+        Self::remove_invite(&env, &team_name, &invitee);
+
+        Ok(())
+    }
+
+    /// Leave a team, queueing the member's stake for withdrawal
+    ///
+    /// The captain role passes to the next member in line. The last member
+    /// leaving disbands the team. Returns the KALE queued, which can be
+    /// withdrawn with `withdraw_unstaked` after the unstake cooldown.
+    pub fn leave_team(env: Env, team_name: String, member: Address) -> Result<i128, Error> {
+        member.require_auth();
+
+        let mut team = Self::load_team(&env, &team_name)?;
+        if !team.members.contains(&member) {
+            return Err(Error::Unauthorized);
+        }
+        if team.members.len() == 1 {
+            return Self::dissolve_team(&env, team);
+        }
+
+        let settled = Self::settle_member(&env, &mut team, &member)?;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team_name.clone()), &team);
+
+        env.events().publish(("member_left", team_name, member, settled), None::<String>);
+
+        Ok(settled)
+    }
+
+    /// Remove a member from the team and queue their stake for withdrawal (captain only)
+    pub fn remove_member(
+        env: Env,
+        team_name: String,
+        captain: Address,
+        member: Address,
+    ) -> Result<i128, Error> {
+        captain.require_auth();
+
+        let mut team = Self::require_role(&env, &team_name, &captain, MemberRole::Captain)?;
+        if member == captain || !team.members.contains(&member) {
+            return Err(Error::Unauthorized);
+        }
+
+        let settled = Self::settle_member(&env, &mut team, &member)?;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team_name.clone()), &team);
+
+        env.events().publish(("member_removed", team_name, member, settled), None::<String>);
+
+        Ok(settled)
+    }
+
+    /// Dissolve a team, queueing every member's stake for withdrawal (captain only)
+    ///
+    /// Returns the total KALE queued.
+    pub fn disband_team(env: Env, team_name: String, captain: Address) -> Result<i128, Error> {
+        captain.require_auth();
+
+        let team = Self::require_role(&env, &team_name, &captain, MemberRole::Captain)?;
+
+        Self::dissolve_team(&env, team)
+    }
+
+    /// Change a member's role to predictor or staker (captain only)
+    pub fn set_member_role(
+        env: Env,
+        team_name: String,
+        captain: Address,
+        member: Address,
+        role: MemberRole,
+    ) -> Result<(), Error> {
+        captain.require_auth();
+
+        if role == MemberRole::Captain {
+            return Err(Error::InvalidRole);
+        }
+
+        let mut team = Self::require_role(&env, &team_name, &captain, MemberRole::Captain)?;
+        if member == captain || !team.members.contains(&member) {
+            return Err(Error::Unauthorized);
+        }
+
+        team.roles.set(member.clone(), role);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team_name.clone()), &team);
+
+        env.events().publish(("member_role_set", team_name, member), role);
+
+        Ok(())
+    }
+
+    /// Hand the captaincy to another member (captain only)
+    ///
+    /// The previous captain stays on as a predictor.
+    pub fn transfer_captain(
+        env: Env,
+        team_name: String,
+        captain: Address,
+        new_captain: Address,
+    ) -> Result<(), Error> {
+        captain.require_auth();
+
+        let mut team = Self::require_role(&env, &team_name, &captain, MemberRole::Captain)?;
+        if new_captain == captain || !team.members.contains(&new_captain) {
+            return Err(Error::Unauthorized);
+        }
+
+        team.roles.set(captain, MemberRole::Predictor);
+        team.roles.set(new_captain.clone(), MemberRole::Captain);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team_name.clone()), &team);
+
+        env.events().publish(("captain_transferred", team_name, new_captain), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the team captain
+    pub fn get_captain(env: Env, team_name: String) -> Option<Address> {
+        let team = Self::load_team(&env, &team_name).ok()?;
+        team.members
+            .iter()
+            .find(|member| team.roles.get(member.clone()) == Some(MemberRole::Captain))
+    }
+
+    /// Get a member's role in a team
+    pub fn get_member_role(env: Env, team_name: String, member: Address) -> Option<MemberRole> {
+        Self::load_team(&env, &team_name).ok()?.roles.get(member)
+    }
+
+    /// Get every member of a team in joining order with their stake
+    pub fn get_team_roster(env: Env, team_name: String) -> Result<Vec<MemberShare>, Error> {
+        let team = Self::load_team(&env, &team_name)?;
+
+        let mut roster = Vec::new(&env);
+        for member in team.members.iter() {
+            let balance = Self::get_member_balance(env.clone(), team_name.clone(), member.clone());
+            let share_bps = if team.total_stake > 0 {
+                (balance.staked * BPS_DENOMINATOR / team.total_stake) as u32
+            } else {
+                0
+            };
+            roster.push_back(MemberShare {
+                role: team.roles.get(member.clone()).unwrap_or(MemberRole::Staker),
+                member,
+                staked: balance.staked,
+                locked: balance.locked,
+                share_bps,
+            });
+        }
+
+        Ok(roster)
+    }
+
+    /// Get a pending invitation, including expired ones not yet cleared
+    pub fn get_invite(env: Env, team_name: String, invitee: Address) -> Option<Invite> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Invites(team_name, invitee))
+    }
+
+    /// Get the names of teams that have invited a user
+    pub fn get_invites(env: Env, invitee: Address) -> Vec<String> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::UserInvites(invitee))
+            .unwrap_or(Vec::new(&env))
+    }
+}
+
+impl CollaborativeDao {
+    /// Store an invitation and index it under the invitee
+    pub(crate) fn write_invite(env: &Env, team_name: &String, inviter: &Address, invitee: &Address) {
+        let invite = Invite {
+            inviter: inviter.clone(),
+            expires_at: env.ledger().timestamp() + INVITE_TTL,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Invites(team_name.clone(), invitee.clone()), &invite);
+
+        let mut pending = Self::get_invites(env.clone(), invitee.clone());
+        if !pending.contains(team_name) {
+            pending.push_back(team_name.clone());
+            env.storage()
+                .persistent()
+                .set(&DataKey::UserInvites(invitee.clone()), &pending);
+
+            let mut invitees = Self::team_invitees(env, team_name);
+            invitees.push_back(invitee.clone());
+            env.storage()
+                .persistent()
+                .set(&DataKey::TeamInvites(team_name.clone()), &invitees);
+        }
+
+        env.events().publish((
+            "member_invited",
+            team_name.clone(),
+            invitee.clone(),
+            invite.expires_at
+        ), None::<String>);
+    }
+
+    fn remove_invite(env: &Env, team_name: &String, invitee: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Invites(team_name.clone(), invitee.clone()));
+
+        let mut pending = Self::get_invites(env.clone(), invitee.clone());
+        if let Some(index) = pending.first_index_of(team_name) {
+            pending.remove(index);
+            env.storage()
+                .persistent()
+                .set(&DataKey::UserInvites(invitee.clone()), &pending);
+        }
+
+        let mut invitees = Self::team_invitees(env, team_name);
+        if let Some(index) = invitees.first_index_of(invitee) {
+            invitees.remove(index);
+            env.storage()
+                .persistent()
+                .set(&DataKey::TeamInvites(team_name.clone()), &invitees);
+        }
+    }
+
+    fn team_invitees(env: &Env, team_name: &String) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TeamInvites(team_name.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn load_team(env: &Env, team_name: &String) -> Result<Team, Error> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Teams(team_name.clone()))
+            .ok_or(Error::TeamNotFound)
+    }
+
+    /// Take a member off the team and queue their stake for withdrawal
+    ///
+    /// Departing stake waits out the unstake cooldown like any other unstake.
+    /// Stake reserved by open predictions cannot be paid out, so members with
+    /// unresolved predictions have to wait for them to settle.
+    fn settle_member(env: &Env, team: &mut Team, member: &Address) -> Result<i128, Error> {
+        let balance = Self::get_member_balance(env.clone(), team.name.clone(), member.clone());
+        if balance.locked > 0 {
+            return Err(Error::OpenPredictions);
+        }
+
+        if balance.staked > 0 {
+            Self::queue_unstake(env, &team.name, member, balance.staked);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::MemberStakes(team.name.clone(), member.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::MemberLocked(team.name.clone(), member.clone()));
+        team.total_stake -= balance.staked;
+
+        if let Some(index) = team.members.first_index_of(member) {
+            team.members.remove(index);
+        }
+
+        // A departing captain hands over to the next member in line
+        if team.roles.get(member.clone()) == Some(MemberRole::Captain) {
+            if let Some(successor) = team.members.get(0) {
+                team.roles.set(successor, MemberRole::Captain);
+            }
+        }
+        team.roles.remove(member.clone());
+        env.storage()
+            .persistent()
+            .remove(&DataKey::UserTeams(member.clone()));
+
+        Ok(balance.staked)
+    }
+
+    /// Settle every member and erase the team with its pending invitations
+    fn dissolve_team(env: &Env, mut team: Team) -> Result<i128, Error> {
+        let team_locked: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TeamLocked(team.name.clone()))
+            .unwrap_or(0);
+        if team_locked > 0 {
+            return Err(Error::OpenPredictions);
+        }
+
+        let mut settled = 0;
+        for member in team.members.clone().iter() {
+            settled += Self::settle_member(env, &mut team, &member)?;
+        }
+
+        for invitee in Self::team_invitees(env, &team.name).iter() {
+            Self::remove_invite(env, &team.name, &invitee);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Teams(team.name.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::TeamLocked(team.name.clone()));
+        env.storage()
+            .persistent()
+            .remove(&DataKey::TeamInvites(team.name.clone()));
+        env.storage()
+            .persistent()
+            .remove(&ProposalKey::TeamVoting(team.name.clone()));
+        Self::unregister_team(env, &team.name);
+
+        env.events().publish(("team_disbanded", team.name, settled), None::<String>);
+
+        Ok(settled)
+    }
+}
@@ -1,12 +1,44 @@
 #![cfg(test)]
 
 use super::*;
+use crate::migration::LegacyKey;
+use crate::reflector::{PriceData, ReflectorClient};
+use mock_reflector::{Asset, MockReflector, MockReflectorClient};
+extern crate std;
+
 use soroban_sdk::{
-    vec, Env, String, 
-    testutils::Address as TestAddress, 
+    vec, BytesN, Env, IntoVal, String, Symbol,
+    testutils::{Address as TestAddress, AuthorizedFunction, AuthorizedInvocation, Events, Ledger},
+    token::{StellarAssetClient, TokenClient},
     Address
 };
 
+/// Stand-in contract code to upgrade to, exporting `add(a: u64, b: u64)`
+///
+/// Pinned by hash; see `test_fixtures/README.md` for where it comes from.
+mod add_u64 {
+    soroban_sdk::contractimport!(
+        file = "test_fixtures/add_u64.wasm",
+        sha256 = "33d12fec8f6f3ddf2eb0ec76ee9a75a9e37d1fa20af35908d90d278af8264311"
+    );
+}
+
+/// Helper function to register the mock Reflector oracle
+/// USD-quoted with 14 decimals and 5 minute rounds, like the Reflector FX feed
+fn setup_reflector(env: &Env) -> (Address, MockReflectorClient<'static>) {
+    let reflector_id = env.register(MockReflector, ());
+    let reflector = MockReflectorClient::new(env, &reflector_id);
+
+    let oracle_admin = Address::generate(env);
+    reflector.initialize(&oracle_admin, &ticker(env, "USD"), &14, &300);
+
+    (reflector_id, reflector)
+}
+
+fn ticker(env: &Env, symbol: &str) -> Asset {
+    Asset::Other(Symbol::new(env, symbol))
+}
+
 /// Helper function to initialize contract with basic setup
 fn setup_contract() -> (Env, Address, CollaborativeDaoClient<'static>) {
     let env = Env::default();
@@ -18,7 +50,7 @@ fn setup_contract() -> (Env, Address, CollaborativeDaoClient<'static>) {
     
     // Create mock addresses
     let admin = Address::generate(&env);
-    let kale_token = Address::generate(&env);
+    let kale_token = create_kale_token(&env);
     let reflector_oracle = Address::generate(&env);
     
     // Initialize contract
@@ -27,6 +59,19 @@ fn setup_contract() -> (Env, Address, CollaborativeDaoClient<'static>) {
     (env, contract_id, client)
 }
 
+/// Helper function to deploy a Stellar asset contract standing in for KALE
+fn create_kale_token(env: &Env) -> Address {
+    let issuer = Address::generate(env);
+    env.register_stellar_asset_contract_v2(issuer).address()
+}
+
+/// Helper function to mint KALE to a member and stake all of it with the team
+fn fund_and_stake(env: &Env, client: &CollaborativeDaoClient, team_name: &String, user: &Address, amount: i128) {
+    let (kale_token, _, _) = client.get_config();
+    StellarAssetClient::new(env, &kale_token.unwrap()).mint(user, &amount);
+    client.stake_kale(team_name, user, &100);
+}
+
 #[test]
 fn test_form_team_success() {
     let (env, _contract_id, client) = setup_contract();
@@ -39,18 +84,24 @@ fn test_form_team_success() {
     // Form team
     client.form_team(&team_name, &members);
     
-    // Verify team was created
+    // Verify team was created with only the creator
     let team = client.get_team(&team_name).unwrap();
     assert_eq!(team.name, team_name);
-    assert_eq!(team.members.len(), 2);
+    assert_eq!(team.members, vec![&env, user1.clone()]);
     assert_eq!(team.total_stake, 0);
     
     // Verify user team membership tracking
     let user1_team = client.get_user_team(&user1);
     assert_eq!(user1_team, Some(team_name.clone()));
     
-    let user2_team = client.get_user_team(&user2);
-    assert_eq!(user2_team, Some(team_name));
+    // The second member is only invited until they accept
+    assert_eq!(client.get_user_team(&user2), None);
+    assert_eq!(client.get_invites(&user2), vec![&env, team_name.clone()]);
+
+    client.accept_invite(&team_name, &user2);
+    assert_eq!(client.get_team(&team_name).unwrap().members.len(), 2);
+    assert_eq!(client.get_user_team(&user2), Some(team_name));
+    assert!(client.get_invites(&user2).is_empty());
 }
 
 #[test]
@@ -62,30 +113,42 @@ fn test_make_prediction_success() {
     let user1 = Address::generate(&env);
     let members = vec![&env, user1.clone()];
     client.form_team(&team_name, &members);
+    fund_and_stake(&env, &client, &team_name, &user1, 5000);
     
     // Make prediction
-    let prediction_id = String::from_str(&env, "pred1");
+    let label = String::from_str(&env, "pred1");
     let asset = String::from_str(&env, "EUR/USD");
     
-    client.make_prediction(
-        &prediction_id,
+    let prediction_id = client.make_prediction(
+        &Some(label.clone()),
         &team_name,
         &asset,
         &true, // prediction: price will rise
         &1000, // stake amount
         &50,   // stake percentage
         &user1,
+        &300,
     );
     
-    // Verify prediction was created
+    // Verify prediction was created under the first ID
+    assert_eq!(prediction_id, 1);
+    assert_eq!(client.get_prediction_id(&label), Some(prediction_id));
     let prediction = client.get_prediction(&prediction_id).unwrap();
     assert_eq!(prediction.team_name, team_name);
     assert_eq!(prediction.asset, asset);
-    assert_eq!(prediction.prediction, true);
+    assert!(prediction.prediction);
     assert_eq!(prediction.stake_amount, 1000);
     assert_eq!(prediction.stake_percentage, 50);
-    assert_eq!(prediction.resolved, false);
+    assert!(!prediction.resolved);
     assert!(prediction.outcome.is_none());
+    assert_eq!(prediction.resolve_after, 300);
+    assert_eq!(prediction.expires_at, 300 + RESOLUTION_WINDOW);
+
+    // Stake is escrowed for the prediction
+    let balance = client.get_user_balance(&user1);
+    assert_eq!(balance, StakeBalance { staked: 5000, locked: 1000, available: 4000 });
+    let team_balance = client.get_team_balance(&team_name);
+    assert_eq!(team_balance, StakeBalance { staked: 5000, locked: 1000, available: 4000 });
 }
 
 #[test]
@@ -97,30 +160,33 @@ fn test_prediction_duplicate_fails() {
     let user1 = Address::generate(&env);
     let members = vec![&env, user1.clone()];
     client.form_team(&team_name, &members);
+    fund_and_stake(&env, &client, &team_name, &user1, 5000);
     
-    let prediction_id = String::from_str(&env, "pred1");
+    let label = Some(String::from_str(&env, "pred1"));
     let asset = String::from_str(&env, "EUR/USD");
     
     // First prediction should succeed
     client.make_prediction(
-        &prediction_id,
+        &label,
         &team_name,
         &asset,
         &true,
         &1000,
         &50,
         &user1,
+        &300,
     );
     
-    // Second prediction with same ID should fail
+    // Second prediction with same label should fail
     let result = client.try_make_prediction(
-        &prediction_id,
+        &label,
         &team_name,
         &asset,
         &false,
         &2000,
         &75,
         &user1,
+        &300,
     );
     
     assert_eq!(result, Err(Ok(Error::PredictionExists)));
@@ -146,6 +212,35 @@ fn test_stake_validation() {
     assert_eq!(result, Err(Ok(Error::Unauthorized)));
 }
 
+#[test]
+fn test_stake_kale_amount() {
+    let (env, _contract_id, client) = setup_contract();
+    let (kale_token, _, _) = client.get_config();
+    let kale_admin = StellarAssetClient::new(&env, &kale_token.unwrap());
+
+    let team_name = String::from_str(&env, "TestTeam");
+    let user1 = Address::generate(&env);
+    client.form_team(&team_name, &vec![&env, user1.clone()]);
+    kale_admin.mint(&user1, &1000);
+
+    // The percentage variant reports what it took
+    assert_eq!(client.stake_kale(&team_name, &user1, &25), 250);
+
+    // Exact amount
+    assert_eq!(client.stake_kale_amount(&team_name, &user1, &300, &300), 300);
+
+    // Bounded: as much as the balance allows up to the maximum
+    assert_eq!(client.try_stake_kale_amount(&team_name, &user1, &500, &800), Err(Ok(Error::LowBalance)));
+    assert_eq!(client.stake_kale_amount(&team_name, &user1, &400, &800), 450);
+    assert_eq!(client.get_member_stake(&team_name, &user1), 1000);
+    assert_eq!(client.get_team(&team_name).unwrap().total_stake, 1000);
+
+    assert_eq!(client.try_stake_kale_amount(&team_name, &user1, &0, &10), Err(Ok(Error::InvalidStakeAmount)));
+    assert_eq!(client.try_stake_kale_amount(&team_name, &user1, &10, &5), Err(Ok(Error::InvalidStakeAmount)));
+    let outsider = Address::generate(&env);
+    assert_eq!(client.try_stake_kale_amount(&team_name, &outsider, &10, &10), Err(Ok(Error::Unauthorized)));
+}
+
 #[test]
 fn test_error_cases() {
     let (env, _contract_id, client) = setup_contract();
@@ -158,9 +253,9 @@ fn test_error_cases() {
     assert!(team.is_none());
     
     // Test getting non-existent prediction
-    let prediction_id = String::from_str(&env, "nonexistent");
-    let prediction = client.get_prediction(&prediction_id);
+    let prediction = client.get_prediction(&42);
     assert!(prediction.is_none());
+    assert!(client.get_prediction_id(&String::from_str(&env, "nonexistent")).is_none());
     
     // Test user with no team
     let user_team = client.get_user_team(&user1);
@@ -173,12 +268,13 @@ fn test_error_cases() {
 
 #[test]
 fn test_configuration() {
-    let (env, _contract_id, client) = setup_contract();
+    let (_env, _contract_id, client) = setup_contract();
     
     // Test getting configuration
-    let (stored_kale, stored_reflector) = client.get_config();
+    let (stored_kale, stored_reflector, stored_admin) = client.get_config();
     assert!(stored_kale.is_some());
     assert!(stored_reflector.is_some());
+    assert!(stored_admin.is_some());
 }
 
 #[test]
@@ -195,10 +291,17 @@ fn test_team_membership_tracking() {
     client.form_team(&team_name1, &vec![&env, user1.clone(), user2.clone()]);
     client.form_team(&team_name2, &vec![&env, user2.clone(), user3.clone()]);
     
-    // Verify team memberships
+    // Verify team memberships; invitations do not move anyone
     assert_eq!(client.get_user_team(&user1), Some(team_name1.clone()));
-    assert_eq!(client.get_user_team(&user2), Some(team_name2.clone())); // Latest team
-    assert_eq!(client.get_user_team(&user3), Some(team_name2));
+    assert_eq!(client.get_user_team(&user2), Some(team_name2.clone()));
+    assert_eq!(client.get_user_team(&user3), None);
+
+    // Creators cannot start a second team
+    let result = client.try_form_team(&String::from_str(&env, "Team3"), &vec![&env, user1.clone()]);
+    assert_eq!(result, Err(Ok(Error::AlreadyInTeam)));
+
+    // Nor can members accept an invitation elsewhere
+    assert_eq!(client.try_accept_invite(&team_name1, &user2), Err(Ok(Error::AlreadyInTeam)));
 }
 
 #[test] 
@@ -211,34 +314,449 @@ fn test_multiple_predictions_workflow() {
     let user2 = Address::generate(&env);
     let members = vec![&env, user1.clone(), user2.clone()];
     client.form_team(&team_name, &members);
+    fund_and_stake(&env, &client, &team_name, &user1, 5000);
     
     // Create multiple predictions
     let predictions = [
-        ("pred1", "EUR/USD", true, 1000),
-        ("pred2", "BTC/USD", false, 2000),
-        ("pred3", "ETH/USD", true, 1500),
+        (Some("pred1"), "EUR/USD", true, 1000),
+        (Some("pred2"), "BTC/USD", false, 2000),
+        (None, "ETH/USD", true, 1500),
     ];
     
-    for (id, asset, prediction, amount) in predictions.iter() {
-        let prediction_id = String::from_str(&env, id);
+    for (index, (label, asset, prediction, amount)) in predictions.iter().enumerate() {
         let asset_str = String::from_str(&env, asset);
         
-        client.make_prediction(
-            &prediction_id,
+        let prediction_id = client.make_prediction(
+            &label.map(|label| String::from_str(&env, label)),
             &team_name,
             &asset_str,
             prediction,
             &(*amount as i128),
             &50,
             &user1,
+            &300,
         );
         
+        // IDs are handed out in order, labelled or not
+        assert_eq!(prediction_id, index as u64 + 1);
+
         // Verify each prediction
         let stored_prediction = client.get_prediction(&prediction_id).unwrap();
         assert_eq!(stored_prediction.asset, asset_str);
         assert_eq!(stored_prediction.prediction, *prediction);
         assert_eq!(stored_prediction.stake_amount, *amount as i128);
     }
+
+    assert_eq!(client.get_user_balance(&user1).locked, 4500);
+    assert_eq!(client.get_team_balance(&team_name).available, 500);
+}
+
+#[test]
+fn test_invite_lifecycle() {
+    let (env, _contract_id, client) = setup_contract();
+
+    let team_name = String::from_str(&env, "TestTeam");
+    let captain = Address::generate(&env);
+    let invitee = Address::generate(&env);
+    let outsider = Address::generate(&env);
+    client.form_team(&team_name, &vec![&env, captain.clone()]);
+
+    // Only members can invite, and only non-members
+    assert_eq!(client.try_invite_member(&team_name, &outsider, &invitee), Err(Ok(Error::Unauthorized)));
+    assert_eq!(client.try_invite_member(&team_name, &captain, &captain), Err(Ok(Error::AlreadyInTeam)));
+
+    // Declined invitations are gone
+    client.invite_member(&team_name, &captain, &invitee);
+    assert_eq!(
+        client.get_invite(&team_name, &invitee),
+        Some(Invite { inviter: captain.clone(), expires_at: INVITE_TTL })
+    );
+    client.decline_invite(&team_name, &invitee);
+    assert_eq!(client.try_accept_invite(&team_name, &invitee), Err(Ok(Error::InviteNotFound)));
+
+    // Revoked invitations too
+    client.invite_member(&team_name, &captain, &invitee);
+    assert_eq!(client.try_revoke_invite(&team_name, &outsider, &invitee), Err(Ok(Error::Unauthorized)));
+    client.revoke_invite(&team_name, &captain, &invitee);
+    assert!(client.get_invites(&invitee).is_empty());
+    assert_eq!(client.try_decline_invite(&team_name, &invitee), Err(Ok(Error::InviteNotFound)));
+
+    // Expired invitations cannot be accepted
+    client.invite_member(&team_name, &captain, &invitee);
+    env.ledger().set_timestamp(INVITE_TTL + 1);
+    assert_eq!(client.try_accept_invite(&team_name, &invitee), Err(Ok(Error::InviteExpired)));
+
+    // Re-inviting refreshes the expiry
+    client.invite_member(&team_name, &captain, &invitee);
+    client.accept_invite(&team_name, &invitee);
+    assert_eq!(client.get_team(&team_name).unwrap().members, vec![&env, captain, invitee.clone()]);
+    assert_eq!(client.get_invite(&team_name, &invitee), None);
+}
+
+#[test]
+fn test_accept_invite_requires_invitee_auth() {
+    let (env, _contract_id, client) = setup_contract();
+
+    let team_name = String::from_str(&env, "TestTeam");
+    let captain = Address::generate(&env);
+    let invitee = Address::generate(&env);
+    client.form_team(&team_name, &vec![&env, captain.clone(), invitee.clone()]);
+
+    client.accept_invite(&team_name, &invitee);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            invitee.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "accept_invite"),
+                    (team_name.clone(), invitee.clone()).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+}
+
+#[test]
+fn test_leave_and_remove_member() {
+    let (env, client, _reflector, team_name, captain) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+
+    let mate = Address::generate(&env);
+    let recruit = Address::generate(&env);
+    for member in [&mate, &recruit] {
+        client.invite_member(&team_name, &captain, member);
+        client.accept_invite(&team_name, member);
+    }
+    fund_and_stake(&env, &client, &team_name, &mate, 1200);
+    fund_and_stake(&env, &client, &team_name, &recruit, 300);
+    assert_eq!(client.get_team(&team_name).unwrap().total_stake, 6500);
+
+    // Only the captain can remove, and not themselves
+    assert_eq!(client.try_remove_member(&team_name, &mate, &recruit), Err(Ok(Error::Unauthorized)));
+    assert_eq!(client.try_remove_member(&team_name, &captain, &captain), Err(Ok(Error::Unauthorized)));
+
+    // Removed members get their stake back after the cooldown
+    assert_eq!(client.remove_member(&team_name, &captain, &recruit), 300);
+    assert_eq!(client.get_pending_unstake(&team_name, &recruit).unwrap().amount, 300);
+    env.ledger().set_timestamp(env.ledger().timestamp() + DEFAULT_UNSTAKE_COOLDOWN);
+    assert_eq!(client.withdraw_unstaked(&team_name, &recruit), 300);
+    assert_eq!(kale.balance(&recruit), 300);
+    assert_eq!(client.get_user_team(&recruit), None);
+    assert_eq!(client.get_user_stake(&recruit), 0);
+
+    // Stake locked by an open prediction keeps a member in
+    predict_eur(&env, &client, &team_name, &mate, "open", true);
+    assert_eq!(client.try_leave_team(&team_name, &mate), Err(Ok(Error::OpenPredictions)));
+    env.ledger().set_timestamp(client.get_prediction(&1).unwrap().expires_at + 1);
+    client.refund_prediction(&1);
+    assert_eq!(client.leave_team(&team_name, &mate), 1200);
+
+    // When the captain leaves, the next member in line takes over
+    client.invite_member(&team_name, &captain, &mate);
+    client.accept_invite(&team_name, &mate);
+    assert_eq!(client.leave_team(&team_name, &captain), 5000);
+    assert_eq!(client.get_captain(&team_name), Some(mate.clone()));
+
+    let team = client.get_team(&team_name).unwrap();
+    assert_eq!(team.members, vec![&env, mate]);
+    assert_eq!(team.total_stake, 0);
+}
+
+#[test]
+fn test_disband_team() {
+    let (env, client, _reflector, team_name, captain) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+
+    let mate = Address::generate(&env);
+    let invitee = Address::generate(&env);
+    client.invite_member(&team_name, &captain, &mate);
+    client.accept_invite(&team_name, &mate);
+    client.invite_member(&team_name, &captain, &invitee);
+    fund_and_stake(&env, &client, &team_name, &mate, 1500);
+
+    // Unresolved predictions block disbanding
+    let prediction_id = predict_eur(&env, &client, &team_name, &mate, "open", true);
+    assert_eq!(client.try_disband_team(&team_name, &mate), Err(Ok(Error::Unauthorized)));
+    assert_eq!(client.try_disband_team(&team_name, &captain), Err(Ok(Error::OpenPredictions)));
+
+    env.ledger().set_timestamp(client.get_prediction(&prediction_id).unwrap().expires_at + 1);
+    client.refund_prediction(&prediction_id);
+
+    // Every member is paid back and the team is gone
+    assert_eq!(client.disband_team(&team_name, &captain), 6500);
+    env.ledger().set_timestamp(env.ledger().timestamp() + DEFAULT_UNSTAKE_COOLDOWN);
+    client.withdraw_unstaked(&team_name, &captain);
+    client.withdraw_unstaked(&team_name, &mate);
+    assert_eq!(kale.balance(&captain), 5000);
+    assert_eq!(kale.balance(&mate), 1500);
+    assert_eq!(client.get_team(&team_name), None);
+    assert_eq!(client.get_user_team(&captain), None);
+    assert_eq!(client.get_user_team(&mate), None);
+    assert_eq!(client.team_count(), 0);
+
+    // Pending invitations go with it, even if the name is reused
+    assert!(client.get_invites(&invitee).is_empty());
+    client.form_team(&team_name, &vec![&env, mate.clone()]);
+    assert_eq!(client.try_accept_invite(&team_name, &invitee), Err(Ok(Error::InviteNotFound)));
+}
+
+#[test]
+fn test_member_roles() {
+    let (env, client, _reflector, team_name, captain) = setup_with_oracle();
+
+    let member = Address::generate(&env);
+    client.invite_member(&team_name, &captain, &member);
+    client.accept_invite(&team_name, &member);
+    assert_eq!(client.get_member_role(&team_name, &captain), Some(MemberRole::Captain));
+    assert_eq!(client.get_member_role(&team_name, &member), Some(MemberRole::Predictor));
+
+    // Team management is reserved to the captain
+    let stranger = Address::generate(&env);
+    assert_eq!(client.try_invite_member(&team_name, &member, &stranger), Err(Ok(Error::Unauthorized)));
+    assert_eq!(
+        client.try_set_member_role(&team_name, &member, &captain, &MemberRole::Staker),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_member_role(&team_name, &captain, &member, &MemberRole::Captain),
+        Err(Ok(Error::InvalidRole))
+    );
+
+    // Stakers can stake but not predict
+    client.set_member_role(&team_name, &captain, &member, &MemberRole::Staker);
+    fund_and_stake(&env, &client, &team_name, &member, 2000);
+    let result = client.try_make_prediction(
+        &None,
+        &team_name,
+        &String::from_str(&env, "EUR/USD"),
+        &true,
+        &1000,
+        &1,
+        &member,
+        &1_200,
+    );
+    assert_eq!(result, Err(Ok(Error::Unauthorized)));
+
+    client.set_member_role(&team_name, &captain, &member, &MemberRole::Predictor);
+    predict_eur(&env, &client, &team_name, &member, "allowed", true);
+
+    // Handing over the captaincy demotes the previous captain to predictor
+    client.transfer_captain(&team_name, &captain, &member);
+    assert_eq!(client.get_captain(&team_name), Some(member.clone()));
+    assert_eq!(client.get_member_role(&team_name, &captain), Some(MemberRole::Predictor));
+    assert_eq!(client.try_remove_member(&team_name, &captain, &member), Err(Ok(Error::Unauthorized)));
+    client.set_member_role(&team_name, &member, &captain, &MemberRole::Staker);
+}
+
+/// Helper function to add a member with the given role and stake to a team
+fn add_member(
+    env: &Env,
+    client: &CollaborativeDaoClient,
+    team_name: &String,
+    captain: &Address,
+    role: MemberRole,
+    stake: i128,
+) -> Address {
+    let member = Address::generate(env);
+    client.invite_member(team_name, captain, &member);
+    client.accept_invite(team_name, &member);
+    if role != MemberRole::Predictor {
+        client.set_member_role(team_name, captain, &member, &role);
+    }
+    if stake > 0 {
+        fund_and_stake(env, client, team_name, &member, stake);
+    }
+    member
+}
+
+/// Assert that a team's member stakes add up to its total stake
+fn assert_stakes_balance(client: &CollaborativeDaoClient, team_name: &String) {
+    let roster = client.get_team_roster(team_name);
+    let staked: i128 = roster.iter().map(|share| share.staked).sum();
+    assert_eq!(staked, client.get_team(team_name).unwrap().total_stake);
+}
+
+#[test]
+fn test_member_stakes_per_team() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let predictor = add_member(&env, &client, &team_name, &captain, MemberRole::Predictor, 3000);
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 2000);
+    assert_stakes_balance(&client, &team_name);
+
+    // Shares are taken against the team total in joining order
+    let roster = client.get_team_roster(&team_name);
+    let shares: std::vec::Vec<_> = roster.iter().map(|share| (share.member, share.role, share.share_bps)).collect();
+    assert_eq!(
+        shares,
+        [
+            (captain.clone(), MemberRole::Captain, 5_000),
+            (predictor.clone(), MemberRole::Predictor, 3_000),
+            (staker.clone(), MemberRole::Staker, 2_000),
+        ]
+    );
+    assert_eq!(client.get_member_stake(&team_name, &predictor), 3000);
+
+    // A winning prediction pays out of the predictor's stake
+    let rise = predict_eur(&env, &client, &team_name, &predictor, "rise", true);
+    assert_eq!(client.get_team_roster(&team_name).get(1).unwrap().locked, 1000);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert_eq!(client.get_member_stake(&team_name, &predictor), 2000);
+    assert_stakes_balance(&client, &team_name);
+
+    client.remove_member(&team_name, &captain, &staker);
+    assert_stakes_balance(&client, &team_name);
+    client.leave_team(&team_name, &predictor);
+    assert_stakes_balance(&client, &team_name);
+    assert_eq!(client.get_member_stake(&team_name, &predictor), 0);
+
+    // Stakes in a new team start from scratch
+    let other_team = String::from_str(&env, "OtherTeam");
+    client.form_team(&other_team, &vec![&env, predictor.clone()]);
+    fund_and_stake(&env, &client, &other_team, &predictor, 700);
+    assert_eq!(client.get_member_stake(&other_team, &predictor), 700);
+    assert_eq!(client.get_member_stake(&team_name, &predictor), 0);
+    assert_eq!(client.get_user_stake(&predictor), 700);
+    assert_stakes_balance(&client, &other_team);
+
+    assert_eq!(client.try_get_team_roster(&String::from_str(&env, "missing")), Err(Ok(Error::TeamNotFound)));
+}
+
+#[test]
+fn test_unstake_cooldown() {
+    let (env, client, _reflector, team_name, captain) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+    client.set_unstake_cooldown(&600);
+
+    // Locked stake cannot be unstaked
+    predict_eur(&env, &client, &team_name, &captain, "open", true);
+    assert_eq!(client.try_request_unstake(&team_name, &captain, &4500), Err(Ok(Error::LowBalance)));
+    assert_eq!(client.try_request_unstake(&team_name, &captain, &0), Err(Ok(Error::InvalidStakeAmount)));
+
+    // Unstaked KALE leaves the team right away but waits out the cooldown
+    assert_eq!(client.request_unstake(&team_name, &captain, &1500), 1_500);
+    assert_eq!(client.get_member_stake(&team_name, &captain), 3500);
+    assert_eq!(client.get_team(&team_name).unwrap().total_stake, 3500);
+    assert_eq!(client.try_withdraw_unstaked(&team_name, &captain), Err(Ok(Error::CooldownActive)));
+
+    // Cancelling restores the stake
+    assert_eq!(client.cancel_unstake(&team_name, &captain), 1500);
+    assert_eq!(client.get_member_stake(&team_name, &captain), 5000);
+    assert_eq!(client.get_pending_unstake(&team_name, &captain), None);
+    assert_eq!(client.try_cancel_unstake(&team_name, &captain), Err(Ok(Error::UnstakeNotFound)));
+
+    // A second request adds up and restarts the cooldown
+    client.request_unstake(&team_name, &captain, &1000);
+    env.ledger().set_timestamp(1_200);
+    assert_eq!(client.request_unstake(&team_name, &captain, &500), 1_800);
+    assert_eq!(
+        client.get_pending_unstake(&team_name, &captain),
+        Some(PendingUnstake { amount: 1500, unlocks_at: 1_800 })
+    );
+    assert_eq!(client.try_withdraw_unstaked(&team_name, &captain), Err(Ok(Error::CooldownActive)));
+
+    env.ledger().set_timestamp(1_800);
+    assert_eq!(client.withdraw_unstaked(&team_name, &captain), 1500);
+    assert_eq!(kale.balance(&captain), 1500);
+    assert_eq!(client.try_withdraw_unstaked(&team_name, &captain), Err(Ok(Error::UnstakeNotFound)));
+    assert_stakes_balance(&client, &team_name);
+}
+
+/// Set 60% quorum, simple majority and a 10 minute vote on a team
+fn configure_voting(client: &CollaborativeDaoClient, team_name: &String, captain: &Address) {
+    let config = VotingConfig { quorum_bps: 6_000, threshold_bps: 5_000, voting_period: 600 };
+    client.set_voting_config(team_name, captain, &config);
+    assert_eq!(client.get_voting_config(team_name), config);
+}
+
+#[test]
+fn test_proposal_approved_by_quorum() {
+    let (env, client, _reflector, team_name, captain) = setup_with_oracle();
+    let predictor = add_member(&env, &client, &team_name, &captain, MemberRole::Predictor, 3000);
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 2000);
+    configure_voting(&client, &team_name, &captain);
+
+    // Voting has to end a submission cutoff before the resolution time
+    let asset = String::from_str(&env, "EUR/USD");
+    let result = client.try_propose_prediction(&team_name, &predictor, &asset, &true, &1000, &1, &1_700);
+    assert_eq!(result, Err(Ok(Error::SubmissionClosed)));
+    let result = client.try_propose_prediction(&team_name, &staker, &asset, &true, &1000, &1, &1_800);
+    assert_eq!(result, Err(Ok(Error::Unauthorized)));
+
+    // The proposer's 30% approves and escrows the stake
+    let proposal_id = client.propose_prediction(&team_name, &predictor, &asset, &true, &1000, &1, &1_800);
+    let proposal = client.get_proposal(&proposal_id).unwrap();
+    assert_eq!(proposal.status, ProposalStatus::Pending);
+    assert_eq!(proposal.approve_weight, 3000);
+    assert_eq!(proposal.team_stake, 10_000);
+    assert_eq!(proposal.voting_deadline, 1_500);
+    assert_eq!(client.get_user_balance(&predictor).locked, 1000);
+    assert_eq!(client.try_vote_prediction(&proposal_id, &predictor, &true), Err(Ok(Error::AlreadyVoted)));
+
+    // Stake moved after proposing neither adds nor removes votes
+    let newcomer = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 4000);
+    assert_eq!(
+        client.try_vote_prediction(&proposal_id, &newcomer, &false),
+        Err(Ok(Error::NoVotingPower))
+    );
+    client.request_unstake(&team_name, &staker, &2000);
+
+    // 50% voting is short of quorum
+    assert_eq!(client.vote_prediction(&proposal_id, &staker, &false), ProposalStatus::Pending);
+    assert_eq!(client.get_proposal(&proposal_id).unwrap().reject_weight, 2000);
+    assert_eq!(client.get_vote(&proposal_id, &staker), Some(false));
+
+    // Everyone voting, 80% in favour
+    assert_eq!(client.vote_prediction(&proposal_id, &captain, &true), ProposalStatus::Approved);
+    let proposal = client.get_proposal(&proposal_id).unwrap();
+    let prediction = client.get_prediction(&proposal.prediction_id.unwrap()).unwrap();
+    assert_eq!(prediction.predictor, predictor);
+    assert_eq!(prediction.stake_amount, 1000);
+    assert_eq!(prediction.resolve_after, 1_800);
+    assert_eq!(client.get_user_balance(&predictor).locked, 1000);
+
+    // Decided proposals take no more votes
+    let late = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 100);
+    assert_eq!(client.try_vote_prediction(&proposal_id, &late, &true), Err(Ok(Error::ProposalClosed)));
+}
+
+#[test]
+fn test_proposal_rejected_or_expired() {
+    let (env, client, _reflector, team_name, captain) = setup_with_oracle();
+    let predictor = add_member(&env, &client, &team_name, &captain, MemberRole::Predictor, 3000);
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 2000);
+    let unstaked = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 0);
+    configure_voting(&client, &team_name, &captain);
+
+    let bad = VotingConfig { quorum_bps: 6_000, threshold_bps: 10_000, voting_period: 600 };
+    assert_eq!(client.try_set_voting_config(&team_name, &captain, &bad), Err(Ok(Error::InvalidVotingConfig)));
+    assert_eq!(client.try_set_voting_config(&team_name, &predictor, &bad), Err(Ok(Error::Unauthorized)));
+
+    // Half the stake against means approvals can no longer be a majority
+    let asset = String::from_str(&env, "EUR/USD");
+    let rejected = client.propose_prediction(&team_name, &predictor, &asset, &true, &1000, &1, &1_800);
+    assert_eq!(client.try_vote_prediction(&rejected, &unstaked, &false), Err(Ok(Error::NoVotingPower)));
+    assert_eq!(client.vote_prediction(&rejected, &staker, &false), ProposalStatus::Pending);
+    assert_eq!(client.vote_prediction(&rejected, &captain, &false), ProposalStatus::Rejected);
+    assert_eq!(client.get_proposal(&rejected).unwrap().prediction_id, None);
+    assert_eq!(client.get_user_balance(&predictor).locked, 0);
+
+    // Undecided proposals release the stake once the deadline passes
+    let expired = client.propose_prediction(&team_name, &captain, &asset, &false, &500, &1, &1_800);
+    assert_eq!(client.try_close_proposal(&expired), Err(Ok(Error::VotingNotEnded)));
+    env.ledger().set_timestamp(1_501);
+    assert_eq!(client.try_vote_prediction(&expired, &predictor, &true), Err(Ok(Error::ProposalClosed)));
+    client.close_proposal(&expired);
+    assert_eq!(client.get_proposal(&expired).unwrap().status, ProposalStatus::Expired);
+    assert_eq!(client.get_user_balance(&captain).locked, 0);
+    assert_eq!(client.try_close_proposal(&expired), Err(Ok(Error::ProposalClosed)));
 }
 
 #[test]
@@ -253,11 +771,1522 @@ fn test_contract_initialization() {
     let kale_token = Address::generate(&env);
     let reflector_oracle = Address::generate(&env);
     
+    // Nothing is configured before initialization
+    assert_eq!(client.get_config(), (None, None, None));
+    let team_name = String::from_str(&env, "TestTeam");
+    let user1 = Address::generate(&env);
+    client.form_team(&team_name, &vec![&env, user1.clone()]);
+    assert_eq!(client.try_stake_kale(&team_name, &user1, &50), Err(Ok(Error::NotInitialized)));
+    assert_eq!(client.try_set_kale_token(&kale_token), Err(Ok(Error::NotInitialized)));
+
     // Test initialization
     client.initialize(&admin, &kale_token, &reflector_oracle);
     
     // Verify configuration was stored
-    let (stored_kale, stored_reflector) = client.get_config();
+    let (stored_kale, stored_reflector, stored_admin) = client.get_config();
     assert_eq!(stored_kale, Some(kale_token));
     assert_eq!(stored_reflector, Some(reflector_oracle));
+    assert_eq!(stored_admin, Some(admin));
+
+    // Initialization cannot be repeated to swap addresses
+    let attacker = Address::generate(&env);
+    let result = client.try_initialize(&attacker, &attacker, &attacker);
+    assert_eq!(result, Err(Ok(Error::AlreadyInitialized)));
+}
+
+#[test]
+fn test_admin_configuration_updates() {
+    let (env, _contract_id, client) = setup_contract();
+    let (_, _, admin) = client.get_config();
+    let admin = admin.unwrap();
+
+    let new_token = create_kale_token(&env);
+    let new_oracle = Address::generate(&env);
+
+    client.set_kale_token(&new_token);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "set_kale_token"),
+                    (new_token.clone(),).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+
+    client.set_reflector_oracle(&new_oracle);
+    let (stored_kale, stored_reflector, _) = client.get_config();
+    assert_eq!(stored_kale, Some(new_token.clone()));
+    assert_eq!(stored_reflector, Some(new_oracle));
+
+    // Without the admin's signature configuration cannot change
+    env.set_auths(&[]);
+    assert!(client.try_set_kale_token(&Address::generate(&env)).is_err());
+    assert!(client.try_set_reflector_oracle(&Address::generate(&env)).is_err());
+    assert!(client.try_transfer_admin(&Address::generate(&env)).is_err());
+    assert_eq!(client.get_config().0, Some(new_token));
+}
+
+#[test]
+fn test_two_step_admin_transfer() {
+    let (env, _contract_id, client) = setup_contract();
+    let (_, _, old_admin) = client.get_config();
+    let new_admin = Address::generate(&env);
+
+    // Nothing to accept yet
+    assert_eq!(client.try_accept_admin(), Err(Ok(Error::Unauthorized)));
+
+    client.transfer_admin(&new_admin);
+    assert_eq!(client.get_pending_admin(), Some(new_admin.clone()));
+    // The old admin remains in charge until the handover is accepted
+    assert_eq!(client.get_config().2, old_admin);
+
+    client.accept_admin();
+    assert_eq!(env.auths()[0].0, new_admin);
+    assert_eq!(client.get_config().2, Some(new_admin));
+    assert_eq!(client.get_pending_admin(), None);
+}
+
+#[test]
+fn test_reflector_client_reads_oracle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (reflector_id, reflector) = setup_reflector(&env);
+
+    reflector.set_price(&ticker(&env, "EUR"), &900, &108_000_000_000_000);
+    reflector.set_price(&ticker(&env, "EUR"), &1_200, &108_500_000_000_000);
+
+    let client = ReflectorClient::new(&env, &reflector_id);
+
+    // Pairs are quoted by their base ticker
+    let last = client
+        .get_last_price(&env, String::from_str(&env, "EUR/USD"))
+        .unwrap();
+    assert_eq!(last, PriceData { price: 108_500_000_000_000, timestamp: 1_200 });
+
+    let historical = client
+        .get_price(&env, String::from_str(&env, "EUR"), 900)
+        .unwrap();
+    assert_eq!(historical, PriceData { price: 108_000_000_000_000, timestamp: 900 });
+
+    assert_eq!(client.get_decimals(&env), 14);
+    assert_eq!(client.get_resolution(&env), 300);
+}
+
+#[test]
+fn test_reflector_client_missing_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (reflector_id, reflector) = setup_reflector(&env);
+    reflector.set_price(&ticker(&env, "EUR"), &900, &108_000_000_000_000);
+
+    let client = ReflectorClient::new(&env, &reflector_id);
+
+    // Unknown asset
+    let result = client.get_last_price(&env, String::from_str(&env, "BTC/USD"));
+    assert_eq!(result, Err(Error::OracleDataUnavailable));
+
+    // Missing round
+    let result = client.get_price(&env, String::from_str(&env, "EUR/USD"), 1_200);
+    assert_eq!(result, Err(Error::OracleDataUnavailable));
+
+    // Not a valid ticker
+    let result = client.get_last_price(&env, String::from_str(&env, "EU R"));
+    assert_eq!(result, Err(Error::OracleDataUnavailable));
+}
+
+#[test]
+fn test_oracle_views() {
+    let (env, client, reflector, _team_name, _user1) = setup_with_oracle();
+    let asset = String::from_str(&env, "EUR/USD");
+
+    assert_eq!(client.get_oracle_decimals(), 14);
+    assert_eq!(client.try_get_last_price(&asset), Err(Ok(Error::OracleDataUnavailable)));
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    assert_eq!(client.get_last_price(&asset), PriceData { price: 108_500, timestamp: 1_200 });
+}
+
+/// Helper to set up a contract wired to the mock oracle with one single-member team
+fn setup_with_oracle() -> (Env, CollaborativeDaoClient<'static>, MockReflectorClient<'static>, String, Address) {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(900);
+
+    let contract_id = env.register(CollaborativeDao, ());
+    let client = CollaborativeDaoClient::new(&env, &contract_id);
+    let (reflector_id, reflector) = setup_reflector(&env);
+
+    let admin = Address::generate(&env);
+    let kale_token = create_kale_token(&env);
+    client.initialize(&admin, &kale_token, &reflector_id);
+
+    let team_name = String::from_str(&env, "TestTeam");
+    let user1 = Address::generate(&env);
+    client.form_team(&team_name, &vec![&env, user1.clone()]);
+    fund_and_stake(&env, &client, &team_name, &user1, 5000);
+
+    (env, client, reflector, team_name, user1)
+}
+
+/// Make an EUR/USD prediction at the current ledger time
+fn predict_eur(
+    env: &Env,
+    client: &CollaborativeDaoClient,
+    team_name: &String,
+    predictor: &Address,
+    label: &str,
+    rise: bool,
+) -> u64 {
+    client.make_prediction(
+        &Some(String::from_str(env, label)),
+        team_name,
+        &String::from_str(env, "EUR/USD"),
+        &rise,
+        &1000,
+        &1,
+        predictor,
+        &(env.ledger().timestamp() + 300),
+    )
+}
+
+/// Find the first nonce with which the predictor can resolve a prediction
+fn mine(client: &CollaborativeDaoClient, prediction_id: u64) -> u64 {
+    let predictor = client.get_prediction(&prediction_id).unwrap().predictor;
+    let asset = client.get_prediction(&prediction_id).unwrap().asset;
+    mine_bits(client, prediction_id, &predictor, client.get_difficulty(&asset))
+}
+
+/// Find the first nonce whose work hash for a miner has exactly `bits` zero bits
+fn mine_bits(client: &CollaborativeDaoClient, prediction_id: u64, miner: &Address, bits: u32) -> u64 {
+    (0..)
+        .find(|nonce| {
+            let hash = client.get_work_hash(&prediction_id, miner, nonce);
+            CollaborativeDao::leading_zero_bits(&hash) == bits
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_mining_hash_commits_to_prediction() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let other = predict_eur(&env, &client, &team_name, &user1, "other", true);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+
+    // Same nonce, different prediction or prices, different hash
+    let hash = client.get_work_hash(&rise, &user1, &7);
+    assert_ne!(hash, client.get_work_hash(&other, &user1, &7));
+    assert_ne!(hash, client.get_work_hash(&rise, &user1, &8));
+    reflector.set_price(&ticker(&env, "EUR"), &1_200, &108_600);
+    assert_ne!(hash, client.get_work_hash(&rise, &user1, &7));
+
+    // A nonce short of the difficulty is rejected
+    client.set_mining_difficulty(&8);
+    let weak = (0..)
+        .find(|nonce| CollaborativeDao::leading_zero_bits(&client.get_work_hash(&rise, &user1, nonce)) < 8)
+        .unwrap();
+    assert_eq!(client.try_resolve_prediction(&rise, &weak), Err(Ok(Error::HashVerificationFailed)));
+
+    let nonce = mine(&client, rise);
+    assert!(CollaborativeDao::leading_zero_bits(&client.get_work_hash(&rise, &user1, &nonce)) >= 8);
+    assert!(client.resolve_prediction(&rise, &nonce));
+
+    assert_eq!(
+        client.try_set_mining_difficulty(&(MAX_MINING_DIFFICULTY + 1)),
+        Err(Ok(Error::InvalidDifficulty))
+    );
+}
+
+#[test]
+fn test_team_mining_boosts_rewards() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let predictor = add_member(&env, &client, &team_name, &captain, MemberRole::Predictor, 2000);
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 1000);
+    fund_reward_pool(&env, &client, 1000);
+
+    let rise = predict_eur(&env, &client, &team_name, &predictor, "rise", true);
+    assert_eq!(client.try_submit_work(&rise, &staker, &0), Err(Ok(Error::ResolutionTooEarly)));
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+
+    // Hashes short of the difficulty and outsiders are rejected
+    let weak = mine_bits(&client, rise, &staker, 3);
+    assert_eq!(client.try_submit_work(&rise, &staker, &weak), Err(Ok(Error::HashVerificationFailed)));
+    let outsider = Address::generate(&env);
+    let outsider_nonce = mine_bits(&client, rise, &outsider, 4);
+    assert_eq!(client.try_submit_work(&rise, &outsider, &outsider_nonce), Err(Ok(Error::Unauthorized)));
+
+    // A nonce is bound to its miner, and only each member's best hash counts
+    let staker_nonce = mine_bits(&client, rise, &staker, 5);
+    assert_ne!(
+        CollaborativeDao::leading_zero_bits(&client.get_work_hash(&rise, &captain, &staker_nonce)),
+        5
+    );
+    assert_eq!(client.submit_work(&rise, &staker, &staker_nonce), 5);
+    assert_eq!(client.submit_work(&rise, &staker, &mine_bits(&client, rise, &staker, 4)), 5);
+    assert_eq!(client.submit_work(&rise, &captain, &mine_bits(&client, rise, &captain, 4)), 4);
+    assert_eq!(client.get_member_work(&rise, &staker), 5);
+    assert_eq!(client.get_team_work(&rise), 32 + 16);
+    assert_eq!(client.get_miners(&rise), vec![&env, staker.clone(), captain.clone()]);
+
+    // 48 hashes of work at difficulty 4 are 3 shares, a 30% boost on the
+    // 100 bonus shared 2:1 by work
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert_eq!(client.get_claimable(&predictor), 1100);
+    assert_eq!(client.get_claimable(&staker), 20);
+    assert_eq!(client.get_claimable(&captain), 10);
+    assert_eq!(client.reward_pool_balance(), 870);
+    assert_solvent(&client);
+
+    assert_eq!(client.try_submit_work(&rise, &staker, &staker_nonce), Err(Ok(Error::AlreadyResolved)));
+}
+
+#[test]
+fn test_adaptive_difficulty() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 1000);
+    let asset = String::from_str(&env, "EUR/USD");
+
+    let invalid = DifficultyConfig {
+        epoch_length: 600,
+        target_submissions: 2,
+        min_difficulty: 8,
+        max_difficulty: 7,
+    };
+    assert_eq!(client.try_set_difficulty_config(&invalid), Err(Ok(Error::InvalidDifficulty)));
+    client.set_difficulty_config(&DifficultyConfig {
+        min_difficulty: 4,
+        ..invalid
+    });
+    client.set_mining_difficulty(&5);
+
+    let rise = predict_eur(&env, &client, &team_name, &captain, "rise", true);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    assert_eq!(client.get_difficulty(&asset), 5);
+    assert_eq!(client.get_difficulty(&String::from_str(&env, "BTC/USD")), 5);
+
+    // Three submissions in epoch 2 beat the target of two
+    client.submit_work(&rise, &staker, &mine_bits(&client, rise, &staker, 5));
+    client.submit_work(&rise, &captain, &mine_bits(&client, rise, &captain, 5));
+    client.submit_work(&rise, &captain, &mine_bits(&client, rise, &captain, 6));
+    assert_eq!(client.get_difficulty(&asset), 5);
+    assert!(client.get_difficulty_history(&asset).is_empty());
+
+    env.ledger().set_timestamp(1_800);
+    assert_eq!(client.get_difficulty(&asset), 6);
+    let epoch_two = EpochDifficulty {
+        epoch: 2,
+        difficulty: 5,
+        submissions: 3,
+    };
+    assert_eq!(client.get_difficulty_history(&asset), vec![&env, epoch_two.clone()]);
+    assert_eq!(
+        client.try_submit_work(&rise, &staker, &mine_bits(&client, rise, &staker, 5)),
+        Err(Ok(Error::HashVerificationFailed))
+    );
+
+    // Resolving counts as a submission; one of two keeps the difficulty,
+    // and the two idle epochs after it lower it by a bit each
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    env.ledger().set_timestamp(3_600);
+    assert_eq!(client.get_difficulty(&asset), 4);
+    let epoch_three = EpochDifficulty {
+        epoch: 3,
+        difficulty: 6,
+        submissions: 1,
+    };
+    assert_eq!(client.get_difficulty_history(&asset), vec![&env, epoch_two, epoch_three]);
+}
+
+#[test]
+fn test_difficulty_floor() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let asset = String::from_str(&env, "EUR/USD");
+
+    // Neither the starting difficulty nor the range may go below the default
+    let config = client.get_difficulty_config();
+    assert_eq!(config.min_difficulty, DEFAULT_MINING_DIFFICULTY);
+    assert_eq!(
+        client.try_set_mining_difficulty(&(DEFAULT_MINING_DIFFICULTY - 1)),
+        Err(Ok(Error::InvalidDifficulty))
+    );
+    assert_eq!(
+        client.try_set_difficulty_config(&DifficultyConfig {
+            min_difficulty: DEFAULT_MINING_DIFFICULTY - 1,
+            ..config.clone()
+        }),
+        Err(Ok(Error::InvalidDifficulty))
+    );
+    client.set_mining_difficulty(&6);
+
+    let rise = predict_eur(&env, &client, &team_name, &captain, "rise", true);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert_eq!(client.get_difficulty(&asset), 6);
+
+    // Thirty idle days would take six bits off many times over, but the
+    // difficulty stops at the floor
+    let later = 1_200 + 30 * config.epoch_length;
+    env.ledger().set_timestamp(later);
+    assert_eq!(client.get_difficulty(&asset), DEFAULT_MINING_DIFFICULTY);
+
+    let fall = predict_eur(&env, &client, &team_name, &captain, "fall", false);
+    reflector.set_series(&ticker(&env, "EUR"), &later, &vec![&env, 108_000, 107_500]);
+    env.ledger().set_timestamp(later + 300);
+    let weak = mine_bits(&client, fall, &captain, DEFAULT_MINING_DIFFICULTY - 1);
+    assert_eq!(client.try_resolve_prediction(&fall, &weak), Err(Ok(Error::HashVerificationFailed)));
+    assert!(client.resolve_prediction(&fall, &mine(&client, fall)));
+    assert_eq!(client.get_difficulty(&asset), DEFAULT_MINING_DIFFICULTY);
+}
+
+#[test]
+fn test_farm_lifecycle() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+    let predictor = add_member(&env, &client, &team_name, &captain, MemberRole::Predictor, 2000);
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 1000);
+    fund_reward_pool(&env, &client, 1000);
+
+    // Planting locks available stake until resolve_after
+    let rise = predict_eur(&env, &client, &team_name, &predictor, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &predictor, "fall", false);
+    assert_eq!(client.try_plant(&rise, &staker, &0), Err(Ok(Error::InvalidStakeAmount)));
+    assert_eq!(client.try_plant(&rise, &staker, &1001), Err(Ok(Error::LowBalance)));
+    assert_eq!(client.plant(&rise, &staker, &600), 600);
+    client.plant(&rise, &captain, &300);
+    assert_eq!(client.plant(&rise, &captain, &100), 400);
+    client.plant(&fall, &staker, &100);
+    assert_eq!(client.get_farm_block(&rise).planted, 1000);
+    assert_eq!(client.get_member_balance(&team_name, &staker).locked, 700);
+    assert_eq!(client.try_leave_team(&team_name, &staker), Err(Ok(Error::OpenPredictions)));
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    let nonce = mine_bits(&client, rise, &staker, 5);
+    let hash = client.get_work_hash(&rise, &staker, &nonce);
+    assert_eq!(client.try_work(&rise, &staker, &hash, &nonce), Err(Ok(Error::ResolutionTooEarly)));
+    env.ledger().set_timestamp(1_200);
+    assert_eq!(client.try_plant(&rise, &staker, &100), Err(Ok(Error::PlantingClosed)));
+
+    // Work must present the nonce's own hash and comes from planted farmers
+    assert_eq!(client.try_work(&rise, &staker, &hash, &(nonce + 1)), Err(Ok(Error::HashVerificationFailed)));
+    assert_eq!(client.try_work(&rise, &predictor, &hash, &nonce), Err(Ok(Error::NotPlanted)));
+    assert_eq!(client.work(&rise, &staker, &hash, &nonce), 5);
+    let captain_nonce = mine_bits(&client, rise, &captain, 4);
+    client.work(&rise, &captain, &client.get_work_hash(&rise, &captain, &captain_nonce), &captain_nonce);
+    let fall_nonce = mine_bits(&client, fall, &staker, 4);
+    client.work(&fall, &staker, &client.get_work_hash(&fall, &staker, &fall_nonce), &fall_nonce);
+    assert_eq!(client.get_planting(&rise, &staker), Some(Planting { amount: 600, zero_bits: Some(5) }));
+    assert_eq!(client.get_farm_block(&rise).weight, 600 * 32 + 400 * 16);
+    assert_eq!(client.get_team_work(&rise), 32 + 16);
+    assert_eq!(client.try_harvest(&rise, &staker), Err(Ok(Error::NotExpired)));
+
+    // The winning block emits 10% of its planted stake, shared 3:1 by
+    // stake times work
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert!(!client.resolve_prediction(&fall, &mine(&client, fall)));
+    assert_eq!(client.get_farm_block(&rise).emission, 100);
+    assert_eq!(client.harvest(&rise, &captain), 25);
+    assert_eq!(client.harvest(&rise, &staker), 75);
+    assert_eq!(kale.balance(&staker), 75);
+    assert_eq!(client.try_harvest(&rise, &staker), Err(Ok(Error::NotPlanted)));
+
+    // A losing block slashes the planted stake, so hedging on both sides
+    // costs more than the emission it earns
+    let slashed = client.get_team_slashed(&team_name);
+    assert_eq!(client.harvest(&fall, &staker), 0);
+    assert_eq!(client.get_team_slashed(&team_name), slashed + 100);
+    assert_eq!(
+        client.get_member_balance(&team_name, &staker),
+        StakeBalance { staked: 900, locked: 0, available: 900 }
+    );
+    assert!(kale.balance(&staker) < 100);
+    assert_eq!(client.get_member_balance(&team_name, &captain).locked, 0);
+    assert_solvent(&client);
+
+    assert_eq!(client.try_set_farm_emission(&(MAX_FARM_EMISSION_BPS + 1)), Err(Ok(Error::InvalidEmission)));
+}
+
+#[test]
+fn test_harvest_closes_expired_block() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let staker = add_member(&env, &client, &team_name, &captain, MemberRole::Staker, 1000);
+
+    let rise = predict_eur(&env, &client, &team_name, &captain, "rise", true);
+    client.plant(&rise, &staker, &600);
+    let expires_at = client.get_prediction(&rise).unwrap().expires_at;
+
+    // The block lost, but nobody resolved it in time
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 107_500]);
+    env.ledger().set_timestamp(expires_at);
+    assert_eq!(client.try_harvest(&rise, &staker), Err(Ok(Error::NotExpired)));
+
+    // Harvesting closes the prediction first, so the planted stake is slashed
+    env.ledger().set_timestamp(expires_at + 1);
+    assert_eq!(client.harvest(&rise, &staker), 0);
+    assert_eq!(client.get_prediction(&rise).unwrap().outcome, Some(false));
+    assert_eq!(client.get_team_slashed(&team_name), 1000 + 600);
+    assert_eq!(
+        client.get_member_balance(&team_name, &staker),
+        StakeBalance { staked: 400, locked: 0, available: 400 }
+    );
+    assert_solvent(&client);
+}
+
+#[test]
+fn test_leading_zero_bits() {
+    let env = Env::default();
+    let mut bytes = [0xffu8; 32];
+    assert_eq!(CollaborativeDao::leading_zero_bits(&BytesN::from_array(&env, &bytes)), 0);
+    bytes[0] = 0;
+    bytes[1] = 0x1f;
+    assert_eq!(CollaborativeDao::leading_zero_bits(&BytesN::from_array(&env, &bytes)), 11);
+    assert_eq!(CollaborativeDao::leading_zero_bits(&BytesN::from_array(&env, &[0u8; 32])), 256);
+}
+
+#[test]
+fn test_resolve_prediction_price_rise() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &user1, "fall", false);
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_200, 108_500]);
+    env.ledger().set_timestamp(1_500);
+
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert!(!client.resolve_prediction(&fall, &mine(&client, fall)));
+
+    let prediction = client.get_prediction(&rise).unwrap();
+    assert!(prediction.resolved);
+    assert_eq!(prediction.outcome, Some(true));
+
+    let result = client.try_resolve_prediction(&rise, &0);
+    assert_eq!(result, Err(Ok(Error::AlreadyResolved)));
+}
+
+#[test]
+fn test_resolve_prediction_price_fall() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &user1, "fall", false);
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_500, 108_000]);
+    env.ledger().set_timestamp(1_200);
+
+    assert!(!client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert!(client.resolve_prediction(&fall, &mine(&client, fall)));
+    assert_eq!(client.get_prediction(&fall).unwrap().outcome, Some(true));
+}
+
+#[test]
+fn test_resolve_prediction_flat_price() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_000]);
+    env.ledger().set_timestamp(1_200);
+
+    // An unchanged price voids the prediction and returns the stake
+    assert!(!client.resolve_prediction(&rise, &mine(&client, rise)));
+    let prediction = client.get_prediction(&rise).unwrap();
+    assert!(prediction.resolved);
+    assert_eq!(prediction.outcome, None);
+    let balance = client.get_member_balance(&team_name, &user1);
+    assert_eq!((balance.staked, balance.locked), (5000, 0));
+    assert_eq!(client.get_team_slashed(&team_name), 0);
+    assert_eq!(client.try_claim_reward(&rise, &user1), Err(Ok(Error::NoReward)));
+}
+
+#[test]
+fn test_resolve_prediction_oracle_guard() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+    let asset = String::from_str(&env, "EUR/USD");
+    assert_eq!(
+        client.get_oracle_guard(&asset),
+        OracleGuard { max_staleness: DEFAULT_MAX_STALENESS, max_deviation_bps: DEFAULT_MAX_DEVIATION_BPS }
+    );
+
+    // Made between rounds, so both prices lie 100 seconds before their times
+    env.ledger().set_timestamp(1_000);
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_300);
+
+    client.set_oracle_guard(&asset, &OracleGuard { max_staleness: 60, max_deviation_bps: 5_000 });
+    let result = client.try_resolve_prediction(&rise, &mine(&client, rise));
+    assert_eq!(result, Err(Ok(Error::StalePrice)));
+
+    // Prices are checked before any work, so nobody mines on rejected prices
+    let difficulty = client.get_difficulty(&asset);
+    let unworked = (0..)
+        .find(|nonce| {
+            CollaborativeDao::leading_zero_bits(&client.get_work_hash(&rise, &user1, nonce)) < difficulty
+        })
+        .unwrap();
+    let result = client.try_resolve_prediction(&rise, &unworked);
+    assert_eq!(result, Err(Ok(Error::StalePrice)));
+
+    // A 46 bps move trips a 40 bps circuit breaker
+    client.set_oracle_guard(&asset, &OracleGuard { max_staleness: 120, max_deviation_bps: 40 });
+    let result = client.try_resolve_prediction(&rise, &mine(&client, rise));
+    assert_eq!(result, Err(Ok(Error::PriceDeviation)));
+    let result = client.try_resolve_prediction(&rise, &unworked);
+    assert_eq!(result, Err(Ok(Error::PriceDeviation)));
+    assert!(!client.get_prediction(&rise).unwrap().resolved);
+
+    client.set_oracle_guard(&asset, &OracleGuard { max_staleness: 120, max_deviation_bps: 50 });
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+}
+
+#[test]
+fn test_resolve_prediction_missing_round() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+
+    // No price was published for the round the prediction was made in
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    reflector.clear_price(&ticker(&env, "EUR"), &900);
+    env.ledger().set_timestamp(1_200);
+
+    let result = client.try_resolve_prediction(&rise, &0);
+    assert_eq!(result, Err(Ok(Error::OracleDataUnavailable)));
+
+    // Publishing the round makes the prediction resolvable
+    reflector.set_price(&ticker(&env, "EUR"), &900, &108_000);
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+}
+
+#[test]
+fn test_make_prediction_escrow_limits() {
+    let (env, _contract_id, client) = setup_contract();
+
+    let team_name = String::from_str(&env, "TestTeam");
+    let user1 = Address::generate(&env);
+    let outsider = Address::generate(&env);
+    client.form_team(&team_name, &vec![&env, user1.clone()]);
+    fund_and_stake(&env, &client, &team_name, &user1, 1500);
+
+    let asset = String::from_str(&env, "EUR/USD");
+    let make = |label: &str, amount: i128, predictor: &Address| {
+        client.try_make_prediction(
+            &Some(String::from_str(&env, label)),
+            &team_name,
+            &asset,
+            &true,
+            &amount,
+            &50,
+            predictor,
+            &(env.ledger().timestamp() + 300),
+        )
+    };
+
+    // Nothing to lock
+    assert_eq!(make("zero", 0, &user1), Err(Ok(Error::InvalidStakeAmount)));
+
+    // Non-members have no stake in the team
+    assert_eq!(make("outsider", 100, &outsider), Err(Ok(Error::Unauthorized)));
+
+    // Locks cannot exceed the available stake
+    assert!(make("pred1", 1000, &user1).is_ok());
+    assert_eq!(make("pred2", 1000, &user1), Err(Ok(Error::LowBalance)));
+    assert!(make("pred2", 500, &user1).is_ok());
+    assert_eq!(client.get_user_balance(&user1).available, 0);
+}
+
+#[test]
+fn test_resolve_prediction_releases_escrow() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &user1, "fall", false);
+    assert_eq!(client.get_user_balance(&user1).locked, 2000);
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+
+    // Losing stake is released and slashed from the staked balance
+    assert!(!client.resolve_prediction(&fall, &mine(&client, fall)));
+    assert_eq!(
+        client.get_user_balance(&user1),
+        StakeBalance { staked: 4000, locked: 1000, available: 3000 }
+    );
+
+    // Winning stake leaves the staked balance to be paid out with the reward
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+    assert_eq!(
+        client.get_user_balance(&user1),
+        StakeBalance { staked: 3000, locked: 0, available: 3000 }
+    );
+    assert_eq!(client.get_team(&team_name).unwrap().total_stake, 3000);
+    assert_eq!(client.get_claimable(&user1), 1100);
+}
+
+#[test]
+fn test_losing_stake_slashed() {
+    let (env, client, reflector, team_name, captain) = setup_with_oracle();
+    let member = add_member(&env, &client, &team_name, &captain, MemberRole::Predictor, 3000);
+
+    assert_eq!(
+        client.try_set_slash_split(&SlashSplit { reward_pool_bps: 8_000, treasury_bps: 3_000 }),
+        Err(Ok(Error::InvalidSplit))
+    );
+    client.set_slash_split(&SlashSplit { reward_pool_bps: 7_500, treasury_bps: 2_500 });
+
+    let fall = predict_eur(&env, &client, &team_name, &member, "fall", false);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    assert!(!client.resolve_prediction(&fall, &mine(&client, fall)));
+
+    let expected_topics: soroban_sdk::Vec<soroban_sdk::Val> = (
+        String::from_str(&env, "stake_slashed"),
+        fall,
+        team_name.clone(),
+        member.clone(),
+        1000i128,
+    )
+        .into_val(&env);
+    assert!(env.events().all().iter().any(|(_, topics, _)| topics == expected_topics));
+
+    assert_eq!(client.get_member_stake(&team_name, &member), 2000);
+    assert_eq!(client.get_team(&team_name).unwrap().total_stake, 7000);
+    assert_stakes_balance(&client, &team_name);
+
+    assert_eq!(client.reward_pool_balance(), 750);
+    assert_eq!(client.treasury_balance(), 250);
+    assert_eq!(client.get_team_slashed(&team_name), 1000);
+    assert_eq!(client.get_claimable(&member), 0);
+}
+
+/// Mint KALE to a sponsor and add it to the reward pool
+fn fund_reward_pool(env: &Env, client: &CollaborativeDaoClient, amount: i128) {
+    let (kale_token, _, _) = client.get_config();
+    let sponsor = Address::generate(env);
+    StellarAssetClient::new(env, &kale_token.unwrap()).mint(&sponsor, &amount);
+    client.fund_reward_pool(&sponsor, &amount);
+}
+
+#[test]
+fn test_treasury_fees_and_pool() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+
+    assert_eq!(client.try_set_treasury_fee(&(MAX_TREASURY_FEE_BPS + 1)), Err(Ok(Error::InvalidFee)));
+    client.set_treasury_fee(&1_000);
+    client.set_slash_split(&SlashSplit { reward_pool_bps: 5_000, treasury_bps: 5_000 });
+    fund_reward_pool(&env, &client, 150);
+
+    // The first bonus takes 100 from the pool, 10 of which go to the treasury;
+    // the second is capped at the 50 left
+    let first = predict_eur(&env, &client, &team_name, &user1, "first", true);
+    let second = predict_eur(&env, &client, &team_name, &user1, "second", true);
+    let lost = predict_eur(&env, &client, &team_name, &user1, "lost", false);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    client.resolve_prediction(&first, &mine(&client, first));
+    client.resolve_prediction(&second, &mine(&client, second));
+    assert_eq!(client.get_claimable(&user1), 1090 + 1045);
+    assert_eq!(client.reward_pool_balance(), 0);
+    assert_eq!(client.treasury_balance(), 15);
+
+    // Half of the slashed stake refills the pool
+    client.resolve_prediction(&lost, &mine(&client, lost));
+    assert_eq!(client.reward_pool_balance(), 500);
+    assert_eq!(client.treasury_balance(), 515);
+
+    // Only the admin withdraws, and never more than the treasury holds
+    let recipient = Address::generate(&env);
+    assert_eq!(client.try_withdraw_treasury(&recipient, &516), Err(Ok(Error::InsufficientTreasury)));
+    assert_eq!(client.withdraw_treasury(&recipient, &500), 15);
+    assert_eq!(kale.balance(&recipient), 500);
+    assert_eq!(client.try_fund_reward_pool(&recipient, &0), Err(Ok(Error::InvalidStakeAmount)));
+
+    // Everything owed is still covered by the contract's KALE
+    client.claim_reward(&first, &user1);
+    client.claim_reward(&second, &user1);
+    assert_eq!(kale.balance(&client.address), client.get_team(&team_name).unwrap().total_stake + 500 + 15);
+}
+
+/// Assert that the contract holds exactly what its ledger says it owes
+fn assert_solvent(client: &CollaborativeDaoClient) {
+    let solvency = client.get_solvency();
+    assert_eq!(solvency.assets, solvency.liabilities);
+}
+
+#[test]
+fn test_solvency_ledger() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+    let member = add_member(&env, &client, &team_name, &user1, MemberRole::Predictor, 2000);
+    fund_reward_pool(&env, &client, 50);
+    assert_solvent(&client);
+    assert_eq!(client.get_solvency().stakes, 7000);
+
+    // Winning stake moves to pending rewards, losing stake to the pool
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &member, "fall", false);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    client.resolve_prediction(&rise, &mine(&client, rise));
+    client.resolve_prediction(&fall, &mine(&client, fall));
+    assert_eq!(
+        client.get_solvency(),
+        Solvency {
+            assets: 7050,
+            stakes: 5000,
+            pending_rewards: 1050,
+            market_entries: 0,
+            reward_pool: 1000,
+            treasury: 0,
+            liabilities: 7050,
+        }
+    );
+
+    client.claim_reward(&rise, &user1);
+    client.request_unstake(&team_name, &member, &500);
+    assert_solvent(&client);
+    env.ledger().set_timestamp(1_200 + DEFAULT_UNSTAKE_COOLDOWN);
+    client.withdraw_unstaked(&team_name, &member);
+    assert_solvent(&client);
+    assert_eq!(client.get_solvency().stakes, 4500);
+
+    // Transfers the contract cannot cover fail with a typed error
+    env.as_contract(&client.address, || {
+        env.storage().persistent().set(&DataKey::Rewards(fall, member.clone()), &1_000_000_i128);
+    });
+    assert_eq!(client.try_claim_reward(&fall, &member), Err(Ok(Error::InsufficientPool)));
+}
+
+#[test]
+fn test_pay_out_keeps_other_liabilities_covered() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+    fund_reward_pool(&env, &client, 50);
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    client.resolve_prediction(&rise, &mine(&client, rise));
+    assert_solvent(&client);
+
+    // The contract loses KALE it owes to stakers
+    kale.burn(&client.address, &100);
+    let solvency = client.get_solvency();
+    assert_eq!(solvency.pending_rewards, 1050);
+    assert_eq!(solvency.assets, solvency.liabilities - 100);
+
+    // The balance covers the reward, but not the stake left behind
+    assert!(solvency.assets >= 1050);
+    assert_eq!(client.try_claim_reward(&rise, &user1), Err(Ok(Error::InsufficientPool)));
+    assert_eq!(client.get_solvency(), solvency);
+
+    // Once the shortfall is made good the reward can be claimed
+    mint_kale(&env, &client, &client.address, 100);
+    assert_eq!(client.claim_reward(&rise, &user1), 1050);
+    assert_solvent(&client);
+}
+
+#[test]
+fn test_claim_reward_once() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &user1, "fall", false);
+
+    // Nothing to claim before resolution
+    assert_eq!(client.try_claim_reward(&rise, &user1), Err(Ok(Error::NoReward)));
+
+    // Fund the 10% bonus
+    fund_reward_pool(&env, &client, 100);
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    client.resolve_prediction(&rise, &mine(&client, rise));
+    client.resolve_prediction(&fall, &mine(&client, fall));
+
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+
+    assert_eq!(client.claim_reward(&rise, &user1), 1100);
+    let event = env.events().all().last().unwrap();
+    let expected_topics: soroban_sdk::Vec<soroban_sdk::Val> = (
+        String::from_str(&env, "reward_claimed"),
+        rise,
+        user1.clone(),
+        1100i128,
+    )
+        .into_val(&env);
+    assert_eq!(event.1, expected_topics);
+    assert_eq!(kale.balance(&user1), 1100);
+    assert!(client.is_reward_claimed(&rise, &user1));
+
+    // Second claim is rejected and pays nothing
+    assert_eq!(client.try_claim_reward(&rise, &user1), Err(Ok(Error::RewardAlreadyClaimed)));
+    assert_eq!(kale.balance(&user1), 1100);
+    assert_eq!(client.get_claimable(&user1), 0);
+
+    // Losing predictions and non-beneficiaries have nothing to claim
+    assert_eq!(client.try_claim_reward(&fall, &user1), Err(Ok(Error::NoReward)));
+    let stranger = Address::generate(&env);
+    assert_eq!(client.try_claim_reward(&rise, &stranger), Err(Ok(Error::NoReward)));
+
+    assert_eq!(client.try_claim_reward(&99, &user1), Err(Ok(Error::PredictionNotFound)));
+}
+
+#[test]
+fn test_get_claimable_aggregates_predictions() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let first = predict_eur(&env, &client, &team_name, &user1, "first", true);
+    let second = predict_eur(&env, &client, &team_name, &user1, "second", true);
+    fund_reward_pool(&env, &client, 200);
+
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    client.resolve_prediction(&first, &mine(&client, first));
+    client.resolve_prediction(&second, &mine(&client, second));
+
+    assert_eq!(client.get_claimable(&user1), 2200);
+
+    client.claim_reward(&second, &user1);
+    assert_eq!(client.get_claimable(&user1), 1100);
+    assert!(!client.is_reward_claimed(&first, &user1));
+}
+
+/// Helper function to mint KALE to an address
+fn mint_kale(env: &Env, client: &CollaborativeDaoClient, to: &Address, amount: i128) {
+    let (kale_token, _, _) = client.get_config();
+    StellarAssetClient::new(env, &kale_token.unwrap()).mint(to, &amount);
+}
+
+#[test]
+fn test_market_round_pro_rata_payout() {
+    let (env, client, reflector, team_a, user1) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+
+    let user2 = Address::generate(&env);
+    let user3 = Address::generate(&env);
+    let user4 = Address::generate(&env);
+    let team_b = String::from_str(&env, "TeamB");
+    let team_c = String::from_str(&env, "TeamC");
+    client.form_team(&team_b, &vec![&env, user3.clone()]);
+    client.form_team(&team_c, &vec![&env, user4.clone(), user2.clone()]);
+    client.accept_invite(&team_c, &user2);
+    for (user, amount) in [(&user1, 100), (&user2, 200), (&user3, 1000), (&user4, 33)] {
+        mint_kale(&env, &client, user, amount);
+    }
+
+    let market_id = client.create_market(&user1, &String::from_str(&env, "EUR/USD"), &300, &500);
+    let round_id = client.open_round(&market_id);
+    assert_eq!(client.get_round(&market_id, &round_id).unwrap().lock_time, 1_200);
+
+    client.enter_round(&market_id, &round_id, &team_a, &user1, &true, &100);
+    client.enter_round(&market_id, &round_id, &team_c, &user2, &true, &200);
+    client.enter_round(&market_id, &round_id, &team_b, &user3, &false, &1000);
+    client.enter_round(&market_id, &round_id, &team_c, &user4, &true, &33);
+
+    let contract_balance = kale.balance(&client.address);
+
+    reflector.set_series(&ticker(&env, "EUR"), &1_200, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_500);
+    assert_eq!(client.settle_round(&market_id, &round_id), RoundStatus::Settled);
+
+    // 5% fee on the losing pool, remainder split pro-rata across 333 winning KALE
+    let round = client.get_round(&market_id, &round_id).unwrap();
+    assert_eq!(round.outcome, Some(true));
+    assert_eq!(round.fee, 50);
+    assert_eq!(round.payout_pool, 1283);
+    assert_eq!(client.treasury_balance(), 50);
+
+    assert_eq!(client.claim_round(&market_id, &round_id, &user1), 385);
+    assert_eq!(client.claim_round(&market_id, &round_id, &user2), 770);
+    // Last winner receives the rounding remainder
+    assert_eq!(client.claim_round(&market_id, &round_id, &user4), 128);
+
+    // Losers and repeat claims get nothing
+    assert_eq!(client.try_claim_round(&market_id, &round_id, &user3), Err(Ok(Error::NoReward)));
+    assert_eq!(
+        client.try_claim_round(&market_id, &round_id, &user1),
+        Err(Ok(Error::RewardAlreadyClaimed))
+    );
+
+    // Tokens are conserved: everything left from the round is the fee
+    assert_eq!(kale.balance(&client.address), contract_balance - 1283);
+    assert_eq!(client.get_solvency().market_entries, 0);
+    assert_solvent(&client);
+    assert_eq!(kale.balance(&user1) + kale.balance(&user2) + kale.balance(&user3) + kale.balance(&user4), 1283);
+    let round = client.get_round(&market_id, &round_id).unwrap();
+    assert_eq!(round.claimed, round.payout_pool);
+    assert_eq!(round.claims_remaining, 0);
+}
+
+#[test]
+fn test_market_round_void_refunds() {
+    let (env, client, _reflector, team_a, user1) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+    mint_kale(&env, &client, &user1, 400);
+
+    let market_id = client.create_market(&user1, &String::from_str(&env, "EUR/USD"), &300, &500);
+    let round_id = client.open_round(&market_id);
+    client.enter_round(&market_id, &round_id, &team_a, &user1, &true, &150);
+    client.enter_round(&market_id, &round_id, &team_a, &user1, &true, &250);
+    assert_eq!(client.get_round_entry(&market_id, &round_id, &user1).unwrap().amount, 400);
+
+    // Claims wait for settlement
+    assert_eq!(
+        client.try_claim_round(&market_id, &round_id, &user1),
+        Err(Ok(Error::RoundNotSettled))
+    );
+
+    env.ledger().set_timestamp(1_500);
+
+    // Nobody took the other side, so the round is void without any prices
+    assert_eq!(client.settle_round(&market_id, &round_id), RoundStatus::Void);
+    assert_eq!(client.claim_round(&market_id, &round_id, &user1), 400);
+    assert_eq!(kale.balance(&user1), 400);
+    assert_eq!(client.treasury_balance(), 0);
+
+    assert_eq!(
+        client.try_settle_round(&market_id, &round_id),
+        Err(Ok(Error::AlreadyResolved))
+    );
+}
+
+#[test]
+fn test_market_round_settlement_deadline() {
+    let (env, client, reflector, team_a, user1) = setup_with_oracle();
+    let (kale_token, _, _) = client.get_config();
+    let kale = TokenClient::new(&env, &kale_token.unwrap());
+
+    let user2 = Address::generate(&env);
+    let team_b = String::from_str(&env, "TeamB");
+    client.form_team(&team_b, &vec![&env, user2.clone()]);
+    mint_kale(&env, &client, &user1, 300);
+    mint_kale(&env, &client, &user2, 200);
+
+    let market_id = client.create_market(&user1, &String::from_str(&env, "EUR/USD"), &300, &500);
+    let round_id = client.open_round(&market_id);
+    client.enter_round(&market_id, &round_id, &team_a, &user1, &true, &300);
+    client.enter_round(&market_id, &round_id, &team_b, &user2, &false, &200);
+
+    // The oracle never published the lock price
+    reflector.set_series(&ticker(&env, "EUR"), &1_200, &vec![&env, 108_000, 108_500]);
+    reflector.clear_price(&ticker(&env, "EUR"), &1_200);
+    env.ledger().set_timestamp(1_500);
+    assert_eq!(
+        client.try_settle_round(&market_id, &round_id),
+        Err(Ok(Error::OracleDataUnavailable))
+    );
+
+    env.ledger().set_timestamp(1_500 + RESOLUTION_WINDOW);
+    assert_eq!(
+        client.try_settle_round(&market_id, &round_id),
+        Err(Ok(Error::OracleDataUnavailable))
+    );
+    assert_eq!(
+        client.try_claim_round(&market_id, &round_id, &user1),
+        Err(Ok(Error::RoundNotSettled))
+    );
+
+    // Past the deadline anyone can void the round and entries are refunded
+    env.ledger().set_timestamp(1_501 + RESOLUTION_WINDOW);
+    assert_eq!(client.settle_round(&market_id, &round_id), RoundStatus::Void);
+    assert_eq!(client.get_round(&market_id, &round_id).unwrap().outcome, None);
+    assert_eq!(client.claim_round(&market_id, &round_id, &user1), 300);
+    assert_eq!(client.claim_round(&market_id, &round_id, &user2), 200);
+    assert_eq!(kale.balance(&user1), 300);
+    assert_eq!(kale.balance(&user2), 200);
+    assert_eq!(client.treasury_balance(), 0);
+    assert_eq!(client.get_solvency().market_entries, 0);
+    assert_solvent(&client);
+}
+
+#[test]
+fn test_market_round_rules() {
+    let (env, client, _reflector, team_a, user1) = setup_with_oracle();
+    let user2 = Address::generate(&env);
+    client.form_team(&String::from_str(&env, "TeamB"), &vec![&env, user2.clone()]);
+    mint_kale(&env, &client, &user1, 1000);
+
+    let asset = String::from_str(&env, "EUR/USD");
+    assert_eq!(client.try_create_market(&user1, &asset, &0, &100), Err(Ok(Error::InvalidHorizon)));
+    assert_eq!(client.try_create_market(&user1, &asset, &300, &1_001), Err(Ok(Error::InvalidFee)));
+    assert_eq!(client.try_open_round(&7), Err(Ok(Error::MarketNotFound)));
+
+    let market_id = client.create_market(&user1, &asset, &300, &100);
+    assert_eq!(client.try_enter_round(&market_id, &0, &team_a, &user1, &true, &10), Err(Ok(Error::RoundNotFound)));
+
+    let round_id = client.open_round(&market_id);
+    assert_eq!(client.try_open_round(&market_id), Err(Ok(Error::RoundInProgress)));
+
+    // Only members can enter for their team, and a team backs a single side
+    assert_eq!(
+        client.try_enter_round(&market_id, &round_id, &team_a, &user2, &true, &10),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        client.try_enter_round(&market_id, &round_id, &team_a, &user1, &true, &0),
+        Err(Ok(Error::InvalidStakeAmount))
+    );
+    client.enter_round(&market_id, &round_id, &team_a, &user1, &true, &10);
+    assert_eq!(
+        client.try_enter_round(&market_id, &round_id, &team_a, &user1, &false, &10),
+        Err(Ok(Error::ConflictingSide))
+    );
+
+    assert_eq!(client.try_settle_round(&market_id, &round_id), Err(Ok(Error::RoundNotClosed)));
+
+    // Entries close at lock time, when the next round may open
+    env.ledger().set_timestamp(1_200);
+    assert_eq!(
+        client.try_enter_round(&market_id, &round_id, &team_a, &user1, &true, &10),
+        Err(Ok(Error::RoundClosed))
+    );
+    assert_eq!(client.open_round(&market_id), 1);
+    assert_eq!(client.get_market(&market_id).unwrap().round_count, 2);
+}
+
+#[test]
+fn test_prediction_submission_window() {
+    let (env, client, _reflector, team_name, user1) = setup_with_oracle();
+
+    let asset = String::from_str(&env, "EUR/USD");
+    let make = |label: &str, resolve_after: u64| {
+        client.try_make_prediction(
+            &Some(String::from_str(&env, label)),
+            &team_name,
+            &asset,
+            &true,
+            &100,
+            &1,
+            &user1,
+            &resolve_after,
+        )
+    };
+
+    // Resolution time must leave at least the submission cutoff
+    assert_eq!(make("past", 600), Err(Ok(Error::SubmissionClosed)));
+    assert_eq!(make("cutoff", 900 + SUBMISSION_CUTOFF - 1), Err(Ok(Error::SubmissionClosed)));
+    assert!(make("edge", 900 + SUBMISSION_CUTOFF).is_ok());
+
+    // And cannot be scheduled arbitrarily far ahead
+    assert_eq!(make("far", 900 + MAX_PREDICTION_HORIZON + 1), Err(Ok(Error::InvalidHorizon)));
+    assert!(make("max", 900 + MAX_PREDICTION_HORIZON).is_ok());
+}
+
+#[test]
+fn test_resolve_prediction_at_horizon() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500, 107_000]);
+
+    // Too early, even though later prices exist
+    env.ledger().set_timestamp(1_199);
+    assert_eq!(client.try_resolve_prediction(&rise, &0), Err(Ok(Error::ResolutionTooEarly)));
+
+    // Priced at the 1_200 horizon, not at the later fall
+    env.ledger().set_timestamp(1_800);
+    assert!(client.resolve_prediction(&rise, &mine(&client, rise)));
+}
+
+#[test]
+fn test_refund_expired_prediction() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let expires_at = client.get_prediction(&rise).unwrap().expires_at;
+    assert_eq!(client.get_user_balance(&user1).locked, 1000);
+
+    // Still resolvable at the end of the window
+    env.ledger().set_timestamp(expires_at);
+    assert_eq!(client.try_refund_prediction(&rise), Err(Ok(Error::NotExpired)));
+
+    // Oracle never published the horizon price; window lapses
+    reflector.set_price(&ticker(&env, "EUR"), &900, &108_000);
+    env.ledger().set_timestamp(expires_at + 1);
+    assert_eq!(client.try_resolve_prediction(&rise, &0), Err(Ok(Error::PredictionExpired)));
+
+    client.refund_prediction(&rise);
+    let prediction = client.get_prediction(&rise).unwrap();
+    assert!(prediction.resolved);
+    assert!(prediction.outcome.is_none());
+    assert_eq!(
+        client.get_user_balance(&user1),
+        StakeBalance { staked: 5000, locked: 0, available: 5000 }
+    );
+
+    assert_eq!(client.try_refund_prediction(&rise), Err(Ok(Error::AlreadyResolved)));
+    assert_eq!(client.try_claim_reward(&rise, &user1), Err(Ok(Error::NoReward)));
+}
+
+#[test]
+fn test_expired_prediction_settles_on_prices() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+    let asset = String::from_str(&env, "EUR/USD");
+
+    let rise = predict_eur(&env, &client, &team_name, &user1, "rise", true);
+    let fall = predict_eur(&env, &client, &team_name, &user1, "fall", false);
+    let expires_at = client.get_prediction(&rise).unwrap().expires_at;
+
+    // The price fell, but nobody resolved the predictions in time
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 107_500]);
+    env.ledger().set_timestamp(expires_at + 1);
+
+    // Prices tripping the circuit breaker are left for the admin to review
+    let guard = client.get_oracle_guard(&asset);
+    client.set_oracle_guard(&asset, &OracleGuard { max_deviation_bps: 40, ..guard.clone() });
+    assert_eq!(client.try_refund_prediction(&rise), Err(Ok(Error::PriceDeviation)));
+    client.set_oracle_guard(&asset, &guard);
+
+    // Letting a losing prediction expire does not escape the slash...
+    client.refund_prediction(&rise);
+    assert_eq!(client.get_prediction(&rise).unwrap().outcome, Some(false));
+    assert_eq!(client.get_team_slashed(&team_name), 1000);
+
+    // ...and a winning one only gets its stake back, without a bonus
+    client.refund_prediction(&fall);
+    assert_eq!(client.get_prediction(&fall).unwrap().outcome, Some(true));
+    assert_eq!(client.try_claim_reward(&fall, &user1), Err(Ok(Error::NoReward)));
+
+    assert_eq!(
+        client.get_user_balance(&user1),
+        StakeBalance { staked: 4000, locked: 0, available: 4000 }
+    );
+    assert_eq!(client.try_refund_prediction(&rise), Err(Ok(Error::AlreadyResolved)));
+    assert_solvent(&client);
+}
+
+#[test]
+fn test_migrate_legacy_predictions() {
+    let (env, client, reflector, team_name, user1) = setup_with_oracle();
+    assert_eq!(client.get_schema_version(), CURRENT_SCHEMA_VERSION);
+
+    let current = predict_eur(&env, &client, &team_name, &user1, "current", true);
+
+    // Entries written by the version 0 contract
+    let legacy_open = String::from_str(&env, "legacy-open");
+    let legacy_done = String::from_str(&env, "legacy-done");
+    let legacy = |resolved: bool| PredictionV0 {
+        team_name: team_name.clone(),
+        asset: String::from_str(&env, "EUR/USD"),
+        prediction: true,
+        stake_amount: 700,
+        stake_percentage: 1,
+        predictor: user1.clone(),
+        timestamp: 900,
+        resolved,
+        outcome: if resolved { Some(false) } else { None },
+    };
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        storage.set(&LegacyKey::Predictions(legacy_open.clone()), &legacy(false));
+        storage.set(&LegacyKey::Predictions(legacy_done.clone()), &legacy(true));
+        storage.remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(client.get_schema_version(), 0);
+
+    let migrated = client.migrate(
+        &vec![&env, team_name.clone()],
+        &vec![&env, legacy_open.clone(), legacy_done.clone(), String::from_str(&env, "current")],
+    );
+    assert_eq!(migrated, 2);
+
+    // The version only moves once the admin finalizes the migration
+    assert_eq!(client.get_schema_version(), 0);
+    assert_eq!(client.finalize_migration(), CURRENT_SCHEMA_VERSION);
+    assert_eq!(client.get_schema_version(), CURRENT_SCHEMA_VERSION);
+
+    // Legacy IDs become labels of newly allocated numeric IDs
+    let open_id = client.get_prediction_id(&legacy_open).unwrap();
+    let done_id = client.get_prediction_id(&legacy_done).unwrap();
+    assert_eq!((current, open_id, done_id), (1, 2, 3));
+
+    // Existing fields survive and the new ones are filled in
+    let open = client.get_prediction(&open_id).unwrap();
+    assert_eq!(open.team_name, team_name);
+    assert_eq!(open.stake_amount, 700);
+    assert_eq!(open.predictor, user1);
+    assert_eq!(open.timestamp, 900);
+    assert_eq!(open.resolve_after, 900 + SUBMISSION_CUTOFF);
+    assert_eq!(open.expires_at, 900 + SUBMISSION_CUTOFF + RESOLUTION_WINDOW);
+    assert!(!open.resolved);
+
+    let done = client.get_prediction(&done_id).unwrap();
+    assert!(done.resolved);
+    assert_eq!(done.outcome, Some(false));
+
+    // The open legacy stake is now escrowed alongside the current prediction
+    assert_eq!(client.get_user_balance(&user1).locked, 1700);
+    assert_eq!(client.get_team(&team_name).unwrap().members, vec![&env, user1.clone()]);
+
+    // Migrated predictions resolve like new ones
+    reflector.set_series(&ticker(&env, "EUR"), &900, &vec![&env, 108_000, 108_500]);
+    env.ledger().set_timestamp(1_200);
+    assert!(client.resolve_prediction(&open_id, &mine(&client, open_id)));
+    assert_eq!(client.get_user_balance(&user1).locked, 1000);
+
+    // Running the migration again is a no-op
+    assert_eq!(client.migrate(&vec![&env], &vec![&env, legacy_open]), 0);
+}
+
+#[test]
+fn test_migrate_rejects_unknown_layout() {
+    let (env, _contract_id, client) = setup_contract();
+
+    let bad_id = String::from_str(&env, "bad");
+    env.as_contract(&client.address, || {
+        env.storage()
+            .persistent()
+            .set(&LegacyKey::Predictions(bad_id.clone()), &42u32);
+    });
+
+    let result = client.try_migrate(&vec![&env], &vec![&env, bad_id]);
+    assert_eq!(result, Err(Ok(Error::MigrationFailed)));
+
+    let missing = String::from_str(&env, "missing");
+    let result = client.try_migrate(&vec![&env, missing], &vec![&env]);
+    assert_eq!(result, Err(Ok(Error::TeamNotFound)));
+}
+
+#[test]
+fn test_upgrade_and_migrate_require_admin() {
+    let (env, _contract_id, client) = setup_contract();
+
+    env.set_auths(&[]);
+    let wasm_hash = soroban_sdk::BytesN::from_array(&env, &[7u8; 32]);
+    assert!(client.try_upgrade(&wasm_hash).is_err());
+    assert!(client.try_migrate(&vec![&env], &vec![&env]).is_err());
+    assert!(client.try_finalize_migration().is_err());
+}
+
+#[test]
+fn test_upgrade_replaces_code() {
+    let (env, _contract_id, client) = setup_contract();
+    let (_, _, admin) = client.get_config();
+
+    let wasm_hash = env.deployer().upload_contract_wasm(add_u64::WASM);
+    client.upgrade(&wasm_hash);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            admin.unwrap(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "upgrade"),
+                    (wasm_hash.clone(),).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    let expected_topics: soroban_sdk::Vec<soroban_sdk::Val> = (
+        String::from_str(&env, "contract_upgraded"),
+        wasm_hash,
+    )
+        .into_val(&env);
+    assert!(env.events().all().iter().any(|(_, topics, _)| topics == expected_topics));
+
+    // The contract now runs the uploaded code
+    assert_eq!(add_u64::Client::new(&env, &client.address).add(&2, &3), 5);
+}
+
+#[test]
+fn test_list_teams_paginated() {
+    let (env, _contract_id, client) = setup_contract();
+    assert_eq!(client.team_count(), 0);
+    assert!(client.list_teams(&0, &10).is_empty());
+
+    let names = ["alpha", "bravo", "charlie", "delta", "echo"];
+    let mut captains = std::vec::Vec::new();
+    for name in names {
+        let captain = Address::generate(&env);
+        let mate = Address::generate(&env);
+        let team_name = String::from_str(&env, name);
+        client.form_team(&team_name, &vec![&env, captain.clone(), mate.clone()]);
+        client.accept_invite(&team_name, &mate);
+        captains.push(captain);
+    }
+    fund_and_stake(&env, &client, &String::from_str(&env, "charlie"), &captains[2], 750);
+
+    assert_eq!(client.team_count(), 5);
+
+    let first_page = client.list_teams(&0, &2);
+    assert_eq!(first_page.len(), 2);
+    assert_eq!(first_page.get(0).unwrap().name, String::from_str(&env, "alpha"));
+    assert_eq!(first_page.get(1).unwrap().name, String::from_str(&env, "bravo"));
+
+    let second_page = client.list_teams(&2, &2);
+    assert_eq!(
+        second_page.get(0).unwrap(),
+        TeamSummary {
+            name: String::from_str(&env, "charlie"),
+            member_count: 2,
+            total_stake: 750,
+        }
+    );
+
+    // Pages are clipped at the end of the registry
+    assert_eq!(client.list_teams(&4, &10).len(), 1);
+    assert!(client.list_teams(&5, &10).is_empty());
+    assert!(client.list_teams(&u32::MAX, &u32::MAX).is_empty());
+}
+
+#[test]
+fn test_team_registry_removal() {
+    let (env, _contract_id, client) = setup_contract();
+
+    for name in ["alpha", "bravo", "charlie"] {
+        client.form_team(&String::from_str(&env, name), &vec![&env, Address::generate(&env)]);
+    }
+
+    // Removing from the middle moves the last team into the gap
+    env.as_contract(&client.address, || {
+        CollaborativeDao::unregister_team(&env, &String::from_str(&env, "alpha"));
+    });
+    assert_eq!(client.team_count(), 2);
+    let names: std::vec::Vec<String> = client.list_teams(&0, &10).iter().map(|t| t.name).collect();
+    assert_eq!(names, [String::from_str(&env, "charlie"), String::from_str(&env, "bravo")]);
+
+    env.as_contract(&client.address, || {
+        CollaborativeDao::unregister_team(&env, &String::from_str(&env, "bravo"));
+        // Unknown teams are ignored
+        CollaborativeDao::unregister_team(&env, &String::from_str(&env, "zulu"));
+    });
+    assert_eq!(client.team_count(), 1);
+    assert_eq!(client.list_teams(&0, &10).get(0).unwrap().name, String::from_str(&env, "charlie"));
+}
+
+#[test]
+fn test_migrate_registers_legacy_teams() {
+    let (env, _contract_id, client) = setup_contract();
+
+    // Team written before the registry and member roles existed
+    let legacy_name = String::from_str(&env, "legacy");
+    let captain = Address::generate(&env);
+    let member = Address::generate(&env);
+    let legacy = TeamV0 {
+        name: legacy_name.clone(),
+        members: vec![&env, captain.clone(), member.clone()],
+        total_stake: 1500,
+    };
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        storage.set(&DataKey::Teams(legacy_name.clone()), &legacy);
+        for (user, staked) in [(&captain, 1000_i128), (&member, 500)] {
+            storage.set(&DataKey::UserTeams(user.clone()), &legacy_name);
+            storage.set(&LegacyKey::UserStakes(user.clone()), &staked);
+        }
+    });
+    assert_eq!(client.team_count(), 0);
+
+    assert_eq!(client.migrate(&vec![&env, legacy_name.clone()], &vec![&env]), 1);
+    assert_eq!(client.team_count(), 1);
+    assert_eq!(client.list_teams(&0, &1).get(0).unwrap().name, legacy_name);
+
+    // The first member leads and everyone else keeps predicting
+    assert_eq!(client.get_captain(&legacy_name), Some(captain.clone()));
+    assert_eq!(client.get_member_role(&legacy_name, &member), Some(MemberRole::Predictor));
+
+    // Per-user stakes move under the team
+    assert_eq!(
+        client.get_member_balance(&legacy_name, &captain),
+        StakeBalance { staked: 1000, locked: 0, available: 1000 }
+    );
+    assert_eq!(client.get_member_stake(&legacy_name, &member), 500);
+    assert_stakes_balance(&client, &legacy_name);
+    assert_eq!(client.get_solvency().stakes, 1500);
+
+    // Already migrated
+    assert_eq!(client.migrate(&vec![&env, legacy_name], &vec![&env]), 0);
+    assert_eq!(client.team_count(), 1);
+    assert_eq!(client.get_solvency().stakes, 1500);
+}
+
+#[test]
+fn test_migrate_voids_uncovered_predictions() {
+    let (env, _contract_id, client) = setup_contract();
+
+    // Version 0 team whose open predictions outgrew the members' stakes
+    let legacy_name = String::from_str(&env, "legacy");
+    let captain = Address::generate(&env);
+    let member = Address::generate(&env);
+    let legacy_team = TeamV0 {
+        name: legacy_name.clone(),
+        members: vec![&env, captain.clone(), member.clone()],
+        total_stake: 1500,
+    };
+    let legacy = |predictor: &Address| PredictionV0 {
+        team_name: legacy_name.clone(),
+        asset: String::from_str(&env, "EUR/USD"),
+        prediction: true,
+        stake_amount: 700,
+        stake_percentage: 50,
+        predictor: predictor.clone(),
+        timestamp: 0,
+        resolved: false,
+        outcome: None,
+    };
+    let labels = vec![
+        &env,
+        String::from_str(&env, "captain-first"),
+        String::from_str(&env, "captain-second"),
+        String::from_str(&env, "member"),
+    ];
+    env.as_contract(&client.address, || {
+        let storage = env.storage().persistent();
+        storage.set(&DataKey::Teams(legacy_name.clone()), &legacy_team);
+        for (user, staked) in [(&captain, 1000_i128), (&member, 500)] {
+            storage.set(&DataKey::UserTeams(user.clone()), &legacy_name);
+            storage.set(&LegacyKey::UserStakes(user.clone()), &staked);
+        }
+        storage.set(&LegacyKey::Predictions(labels.get(0).unwrap()), &legacy(&captain));
+        storage.set(&LegacyKey::Predictions(labels.get(1).unwrap()), &legacy(&captain));
+        storage.set(&LegacyKey::Predictions(labels.get(2).unwrap()), &legacy(&member));
+    });
+    mint_kale(&env, &client, &client.address, 1500);
+
+    // Predictions migrate their team first, so it need not be listed
+    assert_eq!(client.migrate(&vec![&env], &labels), 3);
+    assert_eq!(client.team_count(), 1);
+    assert_solvent(&client);
+
+    // Only the stake the captain still holds is escrowed...
+    let first = client.get_prediction_id(&labels.get(0).unwrap()).unwrap();
+    assert!(!client.get_prediction(&first).unwrap().resolved);
+    assert_eq!(
+        client.get_member_balance(&legacy_name, &captain),
+        StakeBalance { staked: 1000, locked: 700, available: 300 }
+    );
+
+    // ...and predictions beyond it are voided rather than over-locked
+    for (label, predictor) in [(labels.get(1).unwrap(), &captain), (labels.get(2).unwrap(), &member)] {
+        let prediction_id = client.get_prediction_id(&label).unwrap();
+        let prediction = client.get_prediction(&prediction_id).unwrap();
+        assert!(prediction.resolved);
+        assert_eq!(prediction.outcome, None);
+        assert_eq!(client.try_refund_prediction(&prediction_id), Err(Ok(Error::AlreadyResolved)));
+        assert_eq!(client.try_claim_reward(&prediction_id, predictor), Err(Ok(Error::NoReward)));
+    }
+    assert_eq!(
+        client.get_member_balance(&legacy_name, &member),
+        StakeBalance { staked: 500, locked: 0, available: 500 }
+    );
 }
@@ -0,0 +1,266 @@
+use soroban_sdk::{contractimpl, contracttype, token, Address, Env, String};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, Error, BPS_DENOMINATOR,
+};
+
+/// Upper bound on the treasury fee taken from prediction bonuses (20%)
+pub const MAX_TREASURY_FEE_BPS: u32 = 2_000;
+
+/// How slashed stake is divided between the reward pool and the treasury
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SlashSplit {
+    pub reward_pool_bps: u32, // funds bonuses of future winning predictions
+    pub treasury_bps: u32,    // kept by the protocol
+}
+
+/// KALE held by the contract against what it owes
+///
+/// Every liability is KALE the contract must be able to pay out; the reward
+/// pool and treasury are counted because they are earmarked.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Solvency {
+    pub assets: i128,          // KALE balance of the contract
+    pub stakes: i128,          // staked and unstaking KALE of members
+    pub pending_rewards: i128, // recorded, unclaimed prediction rewards
+    pub market_entries: i128,  // market round pools not yet paid out
+    pub reward_pool: i128,
+    pub treasury: i128,
+    pub liabilities: i128,     // sum of the above
+}
+
+/// Storage keys for protocol funds
+#[contracttype]
+#[derive(Clone)]
+pub enum TreasuryKey {
+    /// KALE set aside for prediction bonuses
+    RewardPool,
+    /// KALE owned by the protocol
+    Treasury,
+    /// Division of slashed stake
+    SlashSplit,
+    /// Total stake slashed from a team indexed by team name
+    TeamSlashed(String),
+    /// Treasury fee on prediction bonuses in basis points
+    FeeBps,
+    /// Staked and unstaking KALE of all members
+    Stakes,
+    /// Recorded prediction rewards not yet claimed
+    PendingRewards,
+    /// Market round pools not yet paid out
+    MarketEntries,
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Set how slashed stake is divided (admin only)
+    ///
+    /// The two shares must add up to 10000 basis points.
+    pub fn set_slash_split(env: Env, split: SlashSplit) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if split.reward_pool_bps as i128 + split.treasury_bps as i128 != BPS_DENOMINATOR {
+            return Err(Error::InvalidSplit);
+        }
+
+        env.storage().persistent().set(&TreasuryKey::SlashSplit, &split);
+
+        env.events().publish(("slash_split_updated", split.reward_pool_bps, split.treasury_bps), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get how slashed stake is divided
+    ///
+    /// Until configured, everything slashed funds the reward pool.
+    pub fn get_slash_split(env: Env) -> SlashSplit {
+        env.storage()
+            .persistent()
+            .get(&TreasuryKey::SlashSplit)
+            .unwrap_or(SlashSplit {
+                reward_pool_bps: BPS_DENOMINATOR as u32,
+                treasury_bps: 0,
+            })
+    }
+
+    /// Set the treasury fee taken from prediction bonuses (admin only)
+    ///
+    /// The treasury's cut of slashed stake is set separately through
+    /// `set_slash_split`.
+    pub fn set_treasury_fee(env: Env, fee_bps: u32) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        if fee_bps > MAX_TREASURY_FEE_BPS {
+            return Err(Error::InvalidFee);
+        }
+
+        env.storage().persistent().set(&TreasuryKey::FeeBps, &fee_bps);
+
+        env.events().publish(("treasury_fee_updated", fee_bps), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the treasury fee taken from prediction bonuses
+    pub fn get_treasury_fee(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&TreasuryKey::FeeBps)
+            .unwrap_or(0)
+    }
+
+    /// Add KALE to the reward pool that pays prediction bonuses
+    pub fn fund_reward_pool(env: Env, funder: Address, amount: i128) -> Result<i128, Error> {
+        funder.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
+
+        let kale_token = Self::kale_token(&env)?;
+        token::Client::new(&env, &kale_token).transfer(&funder, &env.current_contract_address(), &amount);
+        Self::credit(&env, &TreasuryKey::RewardPool, amount);
+
+        env.events().publish(("reward_pool_funded", funder, amount), None::<String>);
+
+        Ok(Self::reward_pool_balance(env))
+    }
+
+    /// Transfer KALE out of the treasury (admin only)
+    pub fn withdraw_treasury(env: Env, to: Address, amount: i128) -> Result<i128, Error> {
+        Self::require_admin(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
+        if amount > Self::treasury_balance(env.clone()) {
+            return Err(Error::InsufficientTreasury);
+        }
+
+        Self::pay_out(&env, &to, amount, &TreasuryKey::Treasury)?;
+
+        env.events().publish(("treasury_withdrawn", to, amount), None::<String>);
+
+        Ok(Self::treasury_balance(env))
+    }
+
+    /// Get the KALE set aside for prediction bonuses
+    pub fn reward_pool_balance(env: Env) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&TreasuryKey::RewardPool)
+            .unwrap_or(0)
+    }
+
+    /// Get the KALE owned by the protocol
+    pub fn treasury_balance(env: Env) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&TreasuryKey::Treasury)
+            .unwrap_or(0)
+    }
+
+    /// Get the contract's KALE balance against everything it owes
+    pub fn get_solvency(env: Env) -> Result<Solvency, Error> {
+        let kale_token = Self::kale_token(&env)?;
+        let assets = token::Client::new(&env, &kale_token).balance(&env.current_contract_address());
+
+        let stakes = Self::ledger(&env, &TreasuryKey::Stakes);
+        let pending_rewards = Self::ledger(&env, &TreasuryKey::PendingRewards);
+        let market_entries = Self::ledger(&env, &TreasuryKey::MarketEntries);
+        let reward_pool = Self::reward_pool_balance(env.clone());
+        let treasury = Self::treasury_balance(env.clone());
+
+        Ok(Solvency {
+            assets,
+            stakes,
+            pending_rewards,
+            market_entries,
+            reward_pool,
+            treasury,
+            liabilities: stakes + pending_rewards + market_entries + reward_pool + treasury,
+        })
+    }
+
+    /// Get the total stake slashed from a team's losing predictions
+    pub fn get_team_slashed(env: Env, team_name: String) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&TreasuryKey::TeamSlashed(team_name))
+            .unwrap_or(0)
+    }
+}
+
+impl CollaborativeDao {
+    /// Take a losing prediction's stake from the predictor and divide it
+    /// between the reward pool and the treasury
+    ///
+    /// The treasury share is rounded down; the remainder goes to the pool.
+    pub(crate) fn slash_stake(
+        env: &Env,
+        prediction_id: u64,
+        team_name: &String,
+        predictor: &Address,
+        amount: i128,
+    ) {
+        Self::deduct_stake(env, team_name, predictor, amount);
+        Self::credit(env, &TreasuryKey::Stakes, -amount);
+
+        let split = Self::get_slash_split(env.clone());
+        let to_treasury = amount * split.treasury_bps as i128 / BPS_DENOMINATOR;
+        Self::credit(env, &TreasuryKey::Treasury, to_treasury);
+        Self::credit(env, &TreasuryKey::RewardPool, amount - to_treasury);
+        Self::credit(env, &TreasuryKey::TeamSlashed(team_name.clone()), amount);
+
+        env.events().publish((
+            "stake_slashed",
+            prediction_id,
+            team_name.clone(),
+            predictor.clone(),
+            amount
+        ), None::<String>);
+    }
+
+    /// Take a winning prediction's bonus out of the reward pool and return
+    /// what is left for the predictor after the treasury fee
+    ///
+    /// The bonus is capped by the pool, so an empty pool pays no bonus.
+    pub(crate) fn draw_bonus(env: &Env, bonus: i128) -> i128 {
+        let bonus = bonus.min(Self::reward_pool_balance(env.clone()));
+        let fee = bonus * Self::get_treasury_fee(env.clone()) as i128 / BPS_DENOMINATOR;
+
+        Self::credit(env, &TreasuryKey::RewardPool, -bonus);
+        Self::credit(env, &TreasuryKey::Treasury, fee);
+
+        bonus - fee
+    }
+
+    /// Transfer KALE out of the contract and debit it from a ledger bucket
+    ///
+    /// Fails with `InsufficientPool` when the contract's KALE left after the
+    /// transfer would no longer cover the liabilities outside `from`, so one
+    /// bucket can never be paid out of another's funds.
+    pub(crate) fn pay_out(env: &Env, to: &Address, amount: i128, from: &TreasuryKey) -> Result<(), Error> {
+        let solvency = Self::get_solvency(env.clone())?;
+        if solvency.assets - amount < solvency.liabilities - Self::ledger(env, from) {
+            return Err(Error::InsufficientPool);
+        }
+
+        let kale_token = Self::kale_token(env)?;
+        token::Client::new(env, &kale_token).transfer(&env.current_contract_address(), to, &amount);
+        Self::credit(env, from, -amount);
+
+        Ok(())
+    }
+
+    pub(crate) fn credit(env: &Env, key: &TreasuryKey, amount: i128) {
+        let balance = Self::ledger(env, key);
+        env.storage().persistent().set(key, &(balance + amount));
+    }
+
+    fn ledger(env: &Env, key: &TreasuryKey) -> i128 {
+        env.storage().persistent().get(key).unwrap_or(0)
+    }
+}
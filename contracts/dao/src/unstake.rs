@@ -0,0 +1,155 @@
+use soroban_sdk::{contractimpl, contracttype, Address, Env, String};
+
+use crate::{
+    CollaborativeDao, CollaborativeDaoArgs, CollaborativeDaoClient, DataKey, Error, MemberRole,
+    TreasuryKey,
+};
+
+/// Cooldown applied until the admin configures one (3 days)
+pub const DEFAULT_UNSTAKE_COOLDOWN: u64 = 3 * 24 * 60 * 60;
+
+/// Stake taken out of a team and waiting out the cooldown
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingUnstake {
+    pub amount: i128,
+    pub unlocks_at: u64, // withdrawable from this timestamp
+}
+
+#[contractimpl]
+impl CollaborativeDao {
+    /// Take part of a member's available stake out of the team and return
+    /// when it can be withdrawn
+    ///
+    /// The amount stops counting towards the team right away. Requesting
+    /// again adds to the pending amount and restarts the cooldown.
+    pub fn request_unstake(
+        env: Env,
+        team_name: String,
+        member: Address,
+        amount: i128,
+    ) -> Result<u64, Error> {
+        member.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidStakeAmount);
+        }
+
+        Self::require_role(&env, &team_name, &member, MemberRole::Staker)?;
+
+        // Stake reserved by open predictions stays put
+        let balance = Self::get_member_balance(env.clone(), team_name.clone(), member.clone());
+        if amount > balance.available {
+            return Err(Error::LowBalance);
+        }
+
+        Self::deduct_stake(&env, &team_name, &member, amount);
+        let unlocks_at = Self::queue_unstake(&env, &team_name, &member, amount);
+
+        env.events().publish(("unstake_requested", team_name, member, amount), None::<String>);
+
+        Ok(unlocks_at)
+    }
+
+    /// Transfer a pending unstake to the member once its cooldown has passed
+    pub fn withdraw_unstaked(env: Env, team_name: String, member: Address) -> Result<i128, Error> {
+        member.require_auth();
+
+        let pending = Self::get_pending_unstake(env.clone(), team_name.clone(), member.clone())
+            .ok_or(Error::UnstakeNotFound)?;
+        if env.ledger().timestamp() < pending.unlocks_at {
+            return Err(Error::CooldownActive);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Unstakes(team_name.clone(), member.clone()));
+
+        Self::pay_out(&env, &member, pending.amount, &TreasuryKey::Stakes)?;
+
+        env.events().publish(("unstake_withdrawn", team_name, member, pending.amount), None::<String>);
+
+        Ok(pending.amount)
+    }
+
+    /// Put a pending unstake back into the member's team stake
+    pub fn cancel_unstake(env: Env, team_name: String, member: Address) -> Result<i128, Error> {
+        member.require_auth();
+
+        let pending = Self::get_pending_unstake(env.clone(), team_name.clone(), member.clone())
+            .ok_or(Error::UnstakeNotFound)?;
+        let mut team = Self::require_role(&env, &team_name, &member, MemberRole::Staker)?;
+
+        team.total_stake += pending.amount;
+        env.storage()
+            .persistent()
+            .set(&DataKey::Teams(team_name.clone()), &team);
+
+        let member_stake = Self::get_member_stake(env.clone(), team_name.clone(), member.clone());
+        env.storage().persistent().set(
+            &DataKey::MemberStakes(team_name.clone(), member.clone()),
+            &(member_stake + pending.amount),
+        );
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Unstakes(team_name.clone(), member.clone()));
+
+        env.events().publish(("unstake_cancelled", team_name, member, pending.amount), None::<String>);
+
+        Ok(pending.amount)
+    }
+
+    /// Set the time unstaked KALE waits before it can be withdrawn (admin only)
+    ///
+    /// Applies to requests made from now on.
+    pub fn set_unstake_cooldown(env: Env, cooldown: u64) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::UnstakeCooldown, &cooldown);
+
+        env.events().publish(("unstake_cooldown_updated", cooldown), None::<String>);
+
+        Ok(())
+    }
+
+    /// Get the time unstaked KALE waits before it can be withdrawn
+    pub fn get_unstake_cooldown(env: Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::UnstakeCooldown)
+            .unwrap_or(DEFAULT_UNSTAKE_COOLDOWN)
+    }
+
+    /// Get a member's pending unstake from a team
+    pub fn get_pending_unstake(env: Env, team_name: String, member: Address) -> Option<PendingUnstake> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Unstakes(team_name, member))
+    }
+}
+
+impl CollaborativeDao {
+    /// Add stake already taken out of a team to the member's pending unstake
+    /// and restart its cooldown
+    pub(crate) fn queue_unstake(env: &Env, team_name: &String, member: &Address, amount: i128) -> u64 {
+        let key = DataKey::Unstakes(team_name.clone(), member.clone());
+        let queued = env
+            .storage()
+            .persistent()
+            .get::<_, PendingUnstake>(&key)
+            .map_or(0, |pending| pending.amount);
+
+        let unlocks_at = env.ledger().timestamp() + Self::get_unstake_cooldown(env.clone());
+        env.storage().persistent().set(
+            &key,
+            &PendingUnstake {
+                amount: queued + amount,
+                unlocks_at,
+            },
+        );
+
+        unlocks_at
+    }
+}
@@ -0,0 +1,261 @@
+#![no_std]
+use soroban_sdk::{
+    contract, contractimpl, contracterror, contracttype,
+    Address, Env, Symbol, Vec
+};
+
+#[contract]
+pub struct MockReflector;
+
+/// Error types for the mock Reflector oracle
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// Oracle has already been initialized
+    AlreadyInitialized = 1,
+    /// Oracle has not been initialized
+    NotInitialized = 2,
+    /// Prices must be positive
+    InvalidPrice = 3,
+    /// Resolution must be non-zero
+    InvalidResolution = 4,
+}
+
+/// Asset quoted by the oracle (mirrors Reflector's SEP-40 `Asset`)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    /// Stellar asset identified by its contract address
+    Stellar(Address),
+    /// Off-chain asset identified by symbol (e.g., EUR, BTC)
+    Other(Symbol),
+}
+
+/// Price record returned by the oracle (mirrors Reflector's SEP-40 `PriceData`)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    /// The price in base asset, scaled by 10^decimals
+    pub price: i128,
+    /// The round timestamp in seconds
+    pub timestamp: u64,
+}
+
+/// Storage keys for oracle configuration and price rounds
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    /// Address allowed to publish prices
+    Admin,
+    /// Asset all prices are quoted in
+    Base,
+    /// Price decimals
+    Decimals,
+    /// Seconds between price rounds
+    Resolution,
+    /// Assets with at least one published price
+    Assets,
+    /// Most recent round timestamp across all assets
+    LastTimestamp,
+    /// Price indexed by asset and round timestamp
+    Price(Asset, u64),
+}
+
+#[contractimpl]
+impl MockReflector {
+    /// Initialize the oracle with its quote asset, precision and round length
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        base: Asset,
+        decimals: u32,
+        resolution: u32,
+    ) -> Result<(), Error> {
+        if env.storage().persistent().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+        if resolution == 0 {
+            return Err(Error::InvalidResolution);
+        }
+
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        env.storage().persistent().set(&DataKey::Base, &base);
+        env.storage().persistent().set(&DataKey::Decimals, &decimals);
+        env.storage().persistent().set(&DataKey::Resolution, &resolution);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Assets, &Vec::<Asset>::new(&env));
+
+        Ok(())
+    }
+
+    /// Publish a price for the round containing `timestamp`
+    pub fn set_price(env: Env, asset: Asset, timestamp: u64, price: i128) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        Self::write_price(&env, &asset, timestamp, price)
+    }
+
+    /// Publish consecutive rounds starting at `start`, one price per resolution step
+    pub fn set_series(env: Env, asset: Asset, start: u64, prices: Vec<i128>) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let resolution = Self::resolution(env.clone()) as u64;
+
+        for (i, price) in prices.iter().enumerate() {
+            Self::write_price(&env, &asset, start + i as u64 * resolution, price)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the price for a round, simulating a missed oracle update
+    pub fn clear_price(env: Env, asset: Asset, timestamp: u64) -> Result<(), Error> {
+        Self::require_admin(&env)?;
+        let round = Self::round_timestamp(&env, timestamp);
+        env.storage().persistent().remove(&DataKey::Price(asset, round));
+        Ok(())
+    }
+
+    /// Quote asset of all prices
+    pub fn base(env: Env) -> Asset {
+        env.storage().persistent().get(&DataKey::Base).unwrap()
+    }
+
+    /// Assets with published prices
+    pub fn assets(env: Env) -> Vec<Asset> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Assets)
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Price decimals
+    pub fn decimals(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::Decimals).unwrap_or(0)
+    }
+
+    /// Seconds between price rounds
+    pub fn resolution(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::Resolution).unwrap_or(0)
+    }
+
+    /// Most recent round timestamp
+    pub fn last_timestamp(env: Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::LastTimestamp)
+            .unwrap_or(0)
+    }
+
+    /// Price for the round containing `timestamp`
+    pub fn price(env: Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
+        let round = Self::round_timestamp(&env, timestamp);
+        Self::read_price(&env, &asset, round)
+    }
+
+    /// Price in the most recent round
+    pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+        let last = Self::last_timestamp(env.clone());
+        Self::read_price(&env, &asset, last)
+    }
+
+    /// Prices of the last `records` rounds, newest first, skipping missing rounds
+    pub fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>> {
+        let resolution = Self::resolution(env.clone()) as u64;
+        let mut timestamp = Self::last_timestamp(env.clone());
+        let mut result = Vec::new(&env);
+
+        for _ in 0..records {
+            if let Some(data) = Self::read_price(&env, &asset, timestamp) {
+                result.push_back(data);
+            }
+            if timestamp < resolution {
+                break;
+            }
+            timestamp -= resolution;
+        }
+
+        if result.is_empty() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+
+    /// Time-weighted average of the last `records` rounds, unavailable if any round is missing
+    pub fn twap(env: Env, asset: Asset, records: u32) -> Option<i128> {
+        let prices = Self::prices(env, asset, records)?;
+        if prices.len() != records {
+            return None;
+        }
+
+        let sum: i128 = prices.iter().map(|data| data.price).sum();
+        Some(sum / prices.len() as i128)
+    }
+
+    /// Cross price of `base_asset` quoted in `quote_asset` for the most recent round
+    pub fn x_last_price(env: Env, base_asset: Asset, quote_asset: Asset) -> Option<PriceData> {
+        let base_price = Self::lastprice(env.clone(), base_asset)?;
+        let quote_price = Self::lastprice(env.clone(), quote_asset)?;
+        let scale = 10i128.pow(Self::decimals(env));
+
+        Some(PriceData {
+            price: base_price.price * scale / quote_price.price,
+            timestamp: base_price.timestamp,
+        })
+    }
+
+    fn require_admin(env: &Env) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .ok_or(Error::NotInitialized)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    fn round_timestamp(env: &Env, timestamp: u64) -> u64 {
+        let resolution = Self::resolution(env.clone()) as u64;
+        if resolution == 0 {
+            return timestamp;
+        }
+        timestamp - timestamp % resolution
+    }
+
+    fn read_price(env: &Env, asset: &Asset, round: u64) -> Option<PriceData> {
+        let price: i128 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Price(asset.clone(), round))?;
+        Some(PriceData {
+            price,
+            timestamp: round,
+        })
+    }
+
+    fn write_price(env: &Env, asset: &Asset, timestamp: u64, price: i128) -> Result<(), Error> {
+        if price <= 0 {
+            return Err(Error::InvalidPrice);
+        }
+
+        let round = Self::round_timestamp(env, timestamp);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Price(asset.clone(), round), &price);
+
+        let mut assets = Self::assets(env.clone());
+        if !assets.contains(asset) {
+            assets.push_back(asset.clone());
+            env.storage().persistent().set(&DataKey::Assets, &assets);
+        }
+
+        if round > Self::last_timestamp(env.clone()) {
+            env.storage().persistent().set(&DataKey::LastTimestamp, &round);
+        }
+
+        Ok(())
+    }
+}
+
+mod test;
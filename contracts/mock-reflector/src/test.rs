@@ -0,0 +1,108 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{
+    vec, Env, Symbol,
+    testutils::Address as TestAddress,
+    Address
+};
+
+/// Helper function to register an oracle quoting in USD with 14 decimals and 5 minute rounds
+fn setup_oracle() -> (Env, MockReflectorClient<'static>) {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(MockReflector, ());
+    let client = MockReflectorClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &Asset::Other(Symbol::new(&env, "USD")), &14, &300);
+
+    (env, client)
+}
+
+fn eur(env: &Env) -> Asset {
+    Asset::Other(Symbol::new(env, "EUR"))
+}
+
+#[test]
+fn test_configuration() {
+    let (env, client) = setup_oracle();
+
+    assert_eq!(client.base(), Asset::Other(Symbol::new(&env, "USD")));
+    assert_eq!(client.decimals(), 14);
+    assert_eq!(client.resolution(), 300);
+    assert!(client.assets().is_empty());
+
+    let admin = Address::generate(&env);
+    let result = client.try_initialize(&admin, &eur(&env), &7, &60);
+    assert_eq!(result, Err(Ok(Error::AlreadyInitialized)));
+}
+
+#[test]
+fn test_set_price_rounds_timestamps() {
+    let (env, client) = setup_oracle();
+
+    client.set_price(&eur(&env), &1_000, &108);
+
+    // 1_000 falls in the round starting at 900
+    let data = client.price(&eur(&env), &1_199).unwrap();
+    assert_eq!(data, PriceData { price: 108, timestamp: 900 });
+    assert_eq!(client.lastprice(&eur(&env)), Some(data));
+    assert_eq!(client.last_timestamp(), 900);
+    assert_eq!(client.assets(), vec![&env, eur(&env)]);
+
+    let result = client.try_set_price(&eur(&env), &1_200, &0);
+    assert_eq!(result, Err(Ok(Error::InvalidPrice)));
+}
+
+#[test]
+fn test_series_and_missing_rounds() {
+    let (env, client) = setup_oracle();
+
+    // Rising path over four rounds, then drop the third
+    client.set_series(&eur(&env), &0, &vec![&env, 100, 110, 120, 130]);
+    client.clear_price(&eur(&env), &600);
+
+    assert_eq!(client.price(&eur(&env), &600), None);
+    assert_eq!(client.lastprice(&eur(&env)).unwrap().price, 130);
+
+    let prices = client.prices(&eur(&env), &4).unwrap();
+    assert_eq!(prices.len(), 3);
+    assert_eq!(prices.get(0).unwrap(), PriceData { price: 130, timestamp: 900 });
+    assert_eq!(prices.get(1).unwrap(), PriceData { price: 110, timestamp: 300 });
+
+    // TWAP requires every round
+    assert_eq!(client.twap(&eur(&env), &4), None);
+    assert_eq!(client.twap(&eur(&env), &1), Some(130));
+
+    client.set_price(&eur(&env), &600, &120);
+    assert_eq!(client.twap(&eur(&env), &4), Some(115));
+}
+
+#[test]
+fn test_lastprice_missing_in_latest_round() {
+    let (env, client) = setup_oracle();
+    let btc = Asset::Other(Symbol::new(&env, "BTC"));
+
+    client.set_price(&eur(&env), &0, &100);
+    client.set_price(&btc, &300, &4_000_000);
+
+    // EUR was not published in the latest round
+    assert_eq!(client.lastprice(&eur(&env)), None);
+    assert_eq!(client.prices(&Asset::Other(Symbol::new(&env, "XLM")), &2), None);
+}
+
+#[test]
+fn test_cross_price() {
+    let (env, client) = setup_oracle();
+    let btc = Asset::Other(Symbol::new(&env, "BTC"));
+    let scale = 100_000_000_000_000i128;
+
+    client.set_price(&eur(&env), &0, &(scale * 108 / 100));
+    client.set_price(&btc, &0, &(scale * 43_200));
+
+    let cross = client.x_last_price(&btc, &eur(&env)).unwrap();
+    assert_eq!(cross.price, scale * 40_000);
+    assert_eq!(cross.timestamp, 0);
+}